@@ -200,12 +200,12 @@ fn update_input(
 
     for ev in input_map_event.read() {
         match ev.clone() {
-            // axis_input::InputMapEvent::GamepadConnect { entity, index, name, vendor_id, product_id } => {
-            //     println!("Gamepad connected: {entity} {index} {name:?} {vendor_id:?} {product_id:?}");
-            // }
-            // axis_input::InputMapEvent::GamepadDisconnect { entity, index, name, vendor_id, product_id } => {
-            //     println!("Gamepad disconnected: {entity} {index} {name:?} {vendor_id:?} {product_id:?}");
-            // }
+            axis_input::InputMapMessage::GamepadConnect { entity, owner, name, vendor_id, product_id } => {
+                println!("Gamepad connected: {entity} {owner:?} {name:?} {vendor_id:?} {product_id:?}");
+            }
+            axis_input::InputMapMessage::GamepadDisconnect { entity, owner, name, vendor_id, product_id } => {
+                println!("Gamepad disconnected: {entity} {owner:?} {name:?} {vendor_id:?} {product_id:?}");
+            }
             axis_input::InputMapMessage::ValueChanged { mapping:Mapping::X, val, .. } => {
                 menu.x_val=val;
             }
@@ -242,7 +242,8 @@ fn update_input(
                                 // commands.entity(entity).insert(axis_input::GamepadBindMode(true));
                                 // println!("ok!");
                             }
-                                input_map.kbm_bind_mode=true;
+                                input_map.keyboard_bind_mode=true;
+                                input_map.mouse_bind_mode=true;
                             // commands.entity(entity)
 
                             menu.in_bind_mode=true;
@@ -268,7 +269,8 @@ fn update_input(
                 {
                     commands.entity(entity).entry::<axis_input::GamepadBindMode>().and_modify(|mut c|{c.0=false;});
                 }
-                input_map.kbm_bind_mode=false;
+                input_map.keyboard_bind_mode=false;
+                input_map.mouse_bind_mode=false;
 
                 menu.in_bind_mode=false;
 
@@ -307,7 +309,8 @@ fn update_input(
                     if let Ok((entity,_owner)) = gamepad_owner_query.single() {
                         commands.entity(entity).entry::<axis_input::GamepadBindMode>().and_modify(|mut c|{c.0=false;});
                     }
-                    input_map.kbm_bind_mode=false;
+                    input_map.keyboard_bind_mode=false;
+                    input_map.mouse_bind_mode=false;
 
                     menu.in_bind_mode=false;
                 } else {
@@ -13,4 +13,46 @@ pub struct GamepadOwner(pub i32);
 pub struct GamepadBindMode(pub bool);
 
 #[derive(Component, Debug,  Clone,  )]
-pub struct GamepadDeadZone(pub HashMap<Binding,DeadZone>);
\ No newline at end of file
+pub struct GamepadDeadZone(pub HashMap<Binding,DeadZone>);
+
+//on a gamepad, samples its axes resting noise for rest_secs (sticks should be left alone),
+//  then their full range for range_secs (sticks should be rotated, triggers pressed fully),
+//  then sets the calculated axes in GamepadDeadZone (keeping its other entries) and removes itself
+#[derive(Component, Debug,  Clone,  )]
+pub struct GamepadCalibration {
+    pub rest_secs : f32,
+    pub range_secs : f32,
+    pub margin : f32, //added to the resting noise
+}
+
+impl Default for GamepadCalibration {
+    fn default() -> Self {
+        Self { rest_secs: 2.0, range_secs: 5.0, margin: 0.05 }
+    }
+}
+
+//on a player entity, gives it an owner with these bindings (same as InputMap::owner_bindings),
+//  the owner is removed when the component is removed or the entity despawned
+#[derive(Component, Debug,  Clone,  )]
+pub struct OwnerBindings<M:Send+Sync+'static>(pub HashMap<(M,Vec<Binding>),(f32,f32,f32)>); //[mapping,bindings]=(scale,primary_dead,modifier_dead)
+
+//the owner given to an entity with OwnerBindings, set by the crate
+#[derive(Component, Debug,  Clone, Copy, PartialEq, Eq, )]
+pub struct InputOwner(pub i32);
+
+//on a gamepad, sets its GamepadOwner to the owner of the entity with OwnerBindings
+#[derive(Component, Debug,  Clone, Copy, )]
+pub struct GamepadOwnerEntity(pub Entity);
+
+//on an entity with OwnerBindings, kept updated with its owner's mapping values and pressed dirs
+#[derive(Component, Debug,  Clone,  )]
+pub struct OwnerActionState<M:Send+Sync+'static> {
+    pub values : HashMap<M,f32>, //[mapping]=val, non zero only
+    pub pressed : HashMap<M,i32>, //[mapping]=dir
+}
+
+impl<M:Send+Sync+'static> Default for OwnerActionState<M> {
+    fn default() -> Self {
+        Self { values: Default::default(), pressed: Default::default() }
+    }
+}
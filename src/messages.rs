@@ -1,4 +1,6 @@
-use bevy::prelude::Message;
+use std::collections::HashMap;
+
+use bevy::prelude::{Entity, Message};
 use std::fmt::Debug;
 use super::values::*;
 
@@ -17,19 +19,37 @@ pub struct BindingInputMessage {
 #[derive(Debug,Clone,PartialEq,Message)]//Copy,
 
 pub enum InputMapMessage<M:Debug> {
-    // GamepadConnect{entity:Entity,index:usize,name:String,vendor_id:Option<u16>, product_id:Option<u16>},
-    // GamepadDisconnect{entity:Entity,index:usize,name:String,vendor_id:Option<u16>, product_id:Option<u16>},
+    GamepadConnect{entity:Entity,owner:Option<i32>,name:String,vendor_id:Option<u16>, product_id:Option<u16>},
+    GamepadDisconnect{entity:Entity,owner:Option<i32>,name:String,vendor_id:Option<u16>, product_id:Option<u16>}, //owner is the one it had before disconnecting
     Repeat{mapping:M, dir:i32,delay:f32, owner:i32},
+    MultiTap{mapping:M, dir:i32, count:i32, owner:i32}, //count>=2
+    SequenceCompleted{mapping:M, owner:i32},
     JustPressed{mapping:M, dir:i32, owner:i32},
     JustReleased{mapping:M, dir:i32, owner:i32},
     ValueChanged{mapping:M, val:f32, owner:i32},
     TempValueChanged{mapping:M, val:f32, owner:i32},
 
+    //from binding_interactions
+    Tap{mapping:M, dir:i32, owner:i32},
+    Hold{mapping:M, dir:i32, owner:i32},
+    LongPress{mapping:M, dir:i32, progress:f32, owner:i32}, //progress is 0 when released early
+    PressReleased{mapping:M, dir:i32, owner:i32},
+
     // BindPressed{owner:Option<i32>, device : Device, bindings : Vec<Binding>, },
     BindPressed{device : Device, bindings : Vec<Binding>, },
     //BindReleased{player:i32, device : Device, binding : Binding, },
     // BindReleased{owner:Option<i32>, device : Device, bindings : Vec<Binding>, },
     BindReleased{device : Device, bindings : Vec<Binding>, },
+
+    KbmBindingConflict{binding : Binding, owners : Vec<i32>, },
+
+    ActiveDeviceChanged{owner:i32, device:Device},
+
+    CalibrationProgress{entity:Entity, progress:f32}, //0..1
+    CalibrationFinished{entity:Entity, dead_zones:HashMap<Binding,DeadZone>},
+
+    OwnerJoined{owner:i32, entity:Entity},
+    OwnerLeft{owner:i32, entity:Entity},
 }
 
 // impl<M:Copy+Debug> InputMapEvent<M> {
@@ -33,8 +33,15 @@ where
             .add_message::<BindingInputMessage>()
 
             .add_systems(bevy::app::PreUpdate, (
+                owner_entity_system::<M>,
                 binding_inputs_system::<M>,
+                gamepad_connection_system::<M>,
+                gamepad_calibration_system::<M>,
+                gamepad_join_system::<M>,
                 mapping_event_system::<M>,
+                owner_action_state_system::<M>,
+                input_buffer_system::<M>,
+                sequence_system::<M>,
             ).chain().in_set(InputMapSystems).after(InputSystems)
             // .before(mapping_event_system::<M>))
             // .add_systems(Update,(mapping_event_system::<M>,)
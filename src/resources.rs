@@ -1,11 +1,12 @@
 
 use std::collections::{ HashMap, HashSet};
+use std::hash::Hash;
 // use std::fmt::Debug;
 // use bevy::ecs::R;
 // use bevy::prelude::Entity;
 // use bevy::prelude::IntoSystem;
 
-use bevy::prelude::Resource;
+use bevy::prelude::{Entity, GamepadAxis, KeyCode, Resource};
 
 use super::values::*;
 
@@ -20,12 +21,96 @@ use super::values::*;
 ** or let steam or external apps handle it?
 */
 
+#[derive(Debug,Clone,PartialEq,Eq,Default)]
+pub enum KbmOwner {
+    #[default]
+    Any, //any owner with keyboard/mouse bindings
+    None,
+    Owner(i32),
+    Owners(HashSet<i32>), //shared between owners
+}
+
+impl KbmOwner {
+    pub fn contains(&self,owner:i32) -> bool {
+        match self {
+            Self::Any => true,
+            Self::None => false,
+            Self::Owner(kbm_owner) => *kbm_owner==owner,
+            Self::Owners(kbm_owners) => kbm_owners.contains(&owner),
+        }
+    }
+}
+
+//applied to whatever gamepad the owner has, so can be kept with a player's profile
+#[derive(Debug,Clone,PartialEq,Default)]
+pub struct OwnerGamepadSettings {
+    pub dead_zones : HashMap<Binding,DeadZone>, //a gamepad's GamepadDeadZone overrides these
+    pub inverts : HashSet<GamepadAxis>,
+    pub sensitivities : HashMap<GamepadAxis,f32>, //[axis]=multiplier
+}
+
+//which chords are pressed when chords with the same primary have their modifiers pressed
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default)]
+pub enum ChordResolution {
+    #[default]
+    LongestWins, //only the chords with the most modifiers, eg ctrl+f and not f, also releases active shorter chords contained in it
+    All,
+}
+
+//a modifier is tapped when released without anything else pressed while it was held
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum StickyModifiers {
+    Sticky, //a tapped modifier stays held until the next chord with a non modifier primary is pressed
+    Toggle, //a tapped modifier stays held until tapped again
+}
+
+//steps of mapping dirs that must be held at the same time, eg [[(Y,-1)],[(Y,-1),(X,1)],[(X,1),(Punch,1)]]
+#[derive(Debug,Clone,PartialEq)]
+pub struct InputSequence<M> {
+    pub steps : Vec<Vec<(M,i32)>>,
+    pub max_duration : f32, //secs from the first step to the last
+    pub step_leniency : f32, //max secs between steps
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum InputBuffer {
+    Secs(f32),
+    Frames(u32),
+}
+
 #[derive(Resource)]
 
 pub struct InputMap<M:Eq> {
     pub owner_bindings : HashMap<i32,HashMap<(M,Vec<Binding>),(f32,f32,f32)>>, //[owner][mapping,bindings]=(scale,primary_dead,modifier_dead)
     pub bindings_updated :bool,
+    pub owner_entities : HashMap<i32,Entity>, //[owner]=entity, owners given to entities with OwnerBindings, set by the crate
     pub mapping_repeats : HashMap<M,(f32,f32)>, //[mapping]=(initial_delay, rate)
+    pub sequences : HashMap<M,InputSequence<M>>, //[sequence_mapping]=sequence, sends SequenceCompleted with the sequence_mapping
+    pub mapping_buffers : HashMap<M,InputBuffer>, //[mapping]=duration, JustPressed are kept for that long, until consumed
+    pub buffered_presses : HashMap<(i32,M),(i32,InputBuffer)>, //[owner,mapping]=(dir,remaining), set by the crate
+    pub mapping_multi_taps : HashMap<M,f32>, //[mapping]=max_interval, secs between presses in the same dir to count as another tap
+
+    //[(owner,mapping)], presses toggle a latched value (the press dir) on/off, eg crouch toggle, for accessibility
+    pub owner_toggle_mappings : HashSet<(i32,M)>,
+    pub owner_toggle_vals : HashMap<(i32,M),f32>, //[owner,mapping]=latched_val, set by the crate
+
+    //chords (same as owner_bindings' bindings) whose modifiers must be pressed in order, before the primary,
+    //  eg ctrl+f won't trigger when f is pressed before ctrl, set bindings_updated after changing
+    pub ordered_chords : HashSet<Vec<Binding>>,
+
+    //chords of only a binding that is also another chord's modifier (eg alt with alt+tab),
+    //  are pressed and released on its release, if nothing else was pressed in between
+    pub lone_modifiers_on_release : bool,
+
+    //modifiers are bindings used as modifiers in the owner's chords, for accessibility
+    pub owner_sticky_modifiers : HashMap<i32,StickyModifiers>, //[owner]=mode
+    pub owner_latched_modifiers : HashMap<i32,HashSet<Binding>>, //[owner][modifier], set by the crate
+
+    //[mapping,bindings]=interaction, same keys as owner_bindings', sent as extra messages, set bindings_updated after changing
+    pub binding_interactions : HashMap<(M,Vec<Binding>),BindingInteraction>,
+
+    pub chord_resolution : ChordResolution,
+    pub shared_mappings : HashSet<M>, //mappings whose chords are pressed regardless of longer chords, and don't release them
 
     // // pub bind_mode_excludes : HashSet<Binding>,
     // pub bind_mode_owner_includes : HashMap<i32,HashSet<Binding>>, //[owner][binding]
@@ -39,23 +124,56 @@ pub struct InputMap<M:Eq> {
     pub bind_mode_includes : HashSet<Binding>, //[binding]
     pub bind_mode_excludes : HashSet<Binding>, //[binding]
 
+    pub bind_mode_logical_keys:bool, //bind chains get a key's LogicalKey instead of its Key, when it has a char, otherwise LogicalKeys are left out
+
     pub bind_mode_start_dead:f32,
     pub bind_mode_end_dead:f32,
 
-    pub kbm_bind_mode:bool,
+    pub keyboard_bind_mode:bool,
+    pub mouse_bind_mode:bool,
+
+    //gamepad join/leave, the mappings' gamepad bindings and their dead zones are taken from owner_bindings (of any owner for join)
+    pub join_mapping : Option<M>, //pressed on an ownerless gamepad, gives it the next free owner
+    pub leave_mapping : Option<M>, //pressed on an owned gamepad, removes its owner
+    pub join_max_owners : i32, //owners given on join are 0..join_max_owners
 
-    //no owners for kbm, just anyone who has a binding to it can use it
+    //secs a disconnected gamepad's owner, dead zone and bind mode are kept for,
+    //  to give to a reconnected gamepad with the same name, vendor and product ids
+    pub gamepad_reconnect_grace : f32,
+
+    pub owner_gamepad_settings : HashMap<i32,OwnerGamepadSettings>, //[owner]=settings
+
+    //keyboard and mouse count as the same device for ActiveDeviceChanged, so it's only sent when switching between kbm and gamepads
+    pub owner_active_devices : HashMap<i32,Device>, //[owner]=device, last used by the owner, set by the crate
+    pub active_device_threshold : f32, //min abs input value to change an owner's active device, to ignore stick noise
+    pub active_device_mouse_move_threshold : f32, //min abs mouse move pixels to change an owner's active device
+
+    //[key]=label, of the current keyboard layout, set by the crate
+    //  bevy has no way to query the layout, so a key's label is only known after it has been pressed,
+    //  and is the char it last typed, eg "!" instead of "1" if pressed with shift
+    pub key_labels : HashMap<KeyCode,String>,
+
+    pub scroll_pixels_per_line:f32, //when >0, pixel scrolls (eg trackpads) are also sent as line scrolls, one per that many pixels
+    pub scroll_pixels_reset_secs:f32, //leftover pixels of a partial line are dropped after no pixel scrolls for this long
+
+    //by default no owners for kbm, just anyone who has a binding to it can use it
     //  what about on bind mode?
     //     should bind mode events have an option<owner> or should the event be sent to all owners? can't know all the others though
-    // pub kbm_owner:i32,
+    pub kbm_owner:KbmOwner,
+
+    //when sharing kbm, only send a kbm binding to the owner that has it bound,
+    //  if bound by multiple owners, the lowest owner gets it and a KbmBindingConflict is sent (on bindings updated),
+    //  a key's Key and LogicalKey count as the same binding once its label is known, see key_labels
+    pub kbm_split:bool,
 
     // // pub device_player : HashMap::<Device,i32>,
     // // pub bind_mode_devices:HashSet<Device>, //
     // pub(super) gamepad_devices:Vec<Option<(Entity,String,Option<u16>,Option<u16>)>>,
     // pub(super) gamepad_device_entity_map:HashMap<Entity,usize>,
 
-    // pub owner_kbm_mapping_inverts : HashSet<(i32,M)>,
-    // pub owner_gamepad_mapping_inverts : HashSet<(i32,M)>,
+    //[(owner,mapping)], negates the mapping's values from kbm or gamepad devices, eg invert look y only on gamepad
+    pub owner_kbm_mapping_inverts : HashSet<(i32,M)>,
+    pub owner_gamepad_mapping_inverts : HashSet<(i32,M)>,
     // pub owner_device_mapping_inverts : HashSet<(i32,Device,M)>,
 
 }
@@ -65,7 +183,22 @@ impl<M:Eq> Default for InputMap<M> {
         Self {
             owner_bindings: Default::default(),
             bindings_updated: Default::default(),
+            owner_entities: Default::default(),
             mapping_repeats:Default::default(),
+            sequences:Default::default(),
+            mapping_buffers:Default::default(),
+            buffered_presses:Default::default(),
+            mapping_multi_taps:Default::default(),
+            owner_toggle_mappings:Default::default(),
+            owner_toggle_vals:Default::default(),
+            ordered_chords:Default::default(),
+            lone_modifiers_on_release:false,
+            owner_sticky_modifiers:Default::default(),
+            owner_latched_modifiers:Default::default(),
+            binding_interactions:Default::default(),
+            chord_resolution:ChordResolution::LongestWins,
+            shared_mappings:Default::default(),
+            bind_mode_logical_keys:false,
             bind_mode_start_dead:0.4,
             bind_mode_end_dead:0.2,
             // bind_mode_excludes:HashSet::new(),
@@ -74,12 +207,69 @@ impl<M:Eq> Default for InputMap<M> {
 
             bind_mode_includes:Default::default(),
             bind_mode_excludes:Default::default(),
-            kbm_bind_mode: false,
-            // kbm_owner: 0,
+            keyboard_bind_mode: false,
+            mouse_bind_mode: false,
+            join_mapping: None,
+            leave_mapping: None,
+            join_max_owners: 4,
+            gamepad_reconnect_grace: 30.0,
+            owner_gamepad_settings: Default::default(),
+            owner_active_devices: Default::default(),
+            active_device_threshold: 0.5,
+            active_device_mouse_move_threshold: 4.0,
+            key_labels: Default::default(),
+            scroll_pixels_per_line: 0.0,
+            scroll_pixels_reset_secs: 0.5,
+            kbm_owner: KbmOwner::Any,
+            kbm_split: false,
+            owner_kbm_mapping_inverts: Default::default(),
+            owner_gamepad_mapping_inverts: Default::default(),
+        }
+    }
+}
+
+impl<M:Eq> InputMap<M> {
+    pub fn entity_owner(&self, entity:Entity) -> Option<i32> {
+        self.owner_entities.iter().find_map(|(&owner,&owner_entity)|(owner_entity==entity).then_some(owner))
+    }
+
+    pub fn owner_entity(&self, owner:i32) -> Option<Entity> {
+        self.owner_entities.get(&owner).cloned()
+    }
+
+    //owners used by entities and the kbm owner, so not given out to new entities or joining gamepads
+    pub fn reserved_owners(&self) -> HashSet<i32> {
+        let mut owners=self.owner_entities.keys().cloned().collect::<HashSet<_>>();
+
+        match &self.kbm_owner {
+            KbmOwner::Owner(owner) => { owners.insert(*owner); }
+            KbmOwner::Owners(kbm_owners) => { owners.extend(kbm_owners.iter().cloned()); }
+            KbmOwner::Any|KbmOwner::None => {}
+        }
+
+        owners
+    }
+
+    //label for displaying a binding, keys use the label from the current keyboard layout if it is known
+    pub fn binding_label(&self, binding:Binding) -> String {
+        match binding {
+            Binding::Key(key_code) => self.key_labels.get(&key_code).cloned().unwrap_or_else(||binding.get_inner_string()),
+            _ => binding.get_inner_string(),
         }
     }
 }
 
+impl<M:Eq+Hash+Clone> InputMap<M> {
+    //returns the dir of the buffered press and removes it, eg for a jump pressed slightly before landing
+    pub fn consume_buffered(&mut self, owner:i32, mapping:&M) -> Option<i32> {
+        self.buffered_presses.remove(&(owner,mapping.clone())).map(|(dir,_)|dir)
+    }
+
+    pub fn is_buffered(&self, owner:i32, mapping:&M) -> bool {
+        self.buffered_presses.contains_key(&(owner,mapping.clone()))
+    }
+}
+
 //for binding, if multiple keys pressed, then last key pressed is the primary, and when any of them are released the binding is finished
 
 //need to clear binding_val.player_mapping_bind_groups when set_player_devices, set_player_mapping_bindings ??
@@ -13,9 +13,9 @@ use std::{collections::{HashMap, HashSet}, fmt::Debug, hash::Hash};
 
 use bevy::{ecs::prelude::*, prelude::{Gamepad, GamepadAxis}};
 use bevy::input::gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent,};
-use bevy::input::keyboard::KeyCode;
+use bevy::input::{keyboard::KeyCode, mouse::MouseButton};
 
-use crate::{GamepadBindMode, GamepadDeadZone, GamepadOwner};
+use crate::{GamepadBindMode, GamepadCalibration, GamepadDeadZone, GamepadOwner, GamepadOwnerEntity, InputOwner, OwnerActionState, OwnerBindings};
 
 use super::resources::*;
 use super::messages::*;
@@ -26,29 +26,205 @@ fn use_dead_zone(value:f32,dead_zone:Option<&DeadZone>) -> f32 {
         return value;
     };
 
+    let value=value-dead_zone.center;
     let pos_min=dead_zone.pos_min.max(dead_zone.neg_min);
     let neg_min=dead_zone.neg_min.min(dead_zone.pos_min);
     let pos_max=dead_zone.pos_max.max(pos_min);
     let neg_max=dead_zone.neg_max.min(neg_min);
 
+    //rescale so output ramps from 0 at min to 1 at max, pos and neg ranges can differ
     if value > pos_min {
         let len=pos_max-pos_min;
 
         if len>0.0 {
-            return value.clamp(pos_min,pos_max)/len;
+            return (value.min(pos_max)-pos_min)/len;
         }
 
     } else if value < neg_min {
-        let len=neg_max-neg_min;
+        let len=neg_min-neg_max;
 
         if len>0.0 {
-            return value.clamp(neg_max,neg_min)/len;
+            return (value.max(neg_max)-neg_min)/len;
         }
     }
 
     0.0
 }
 
+//dead zone from the gamepad's component, or its owner's settings
+fn gamepad_settings<'a>(
+    entity:Entity,
+    binding:Binding,
+    gamepad_query:&'a Query<(Option<&GamepadDeadZone>,Option<&GamepadOwner>)>,
+    owner_gamepad_settings:&'a HashMap<i32,OwnerGamepadSettings>,
+) -> (Option<&'a DeadZone>,Option<&'a OwnerGamepadSettings>) {
+    let Ok((dead_zones,owner))=gamepad_query.get(entity) else {
+        return (None,None);
+    };
+
+    let owner_settings=owner.and_then(|owner|owner_gamepad_settings.get(&owner.0));
+    let dead_zone=dead_zones.and_then(|dead_zones|dead_zones.0.get(&binding))
+        .or_else(||owner_settings.and_then(|owner_settings|owner_settings.dead_zones.get(&binding)));
+
+    (dead_zone,owner_settings)
+}
+
+//send value change, and press/release if dir changed
+fn send_val_changes<M: Send + Sync + 'static + Clone + Debug>(
+    mapping_events:&mut Vec<InputMapMessage<M>>,
+    mapping:&M, owner:i32, last_val:f32, cur_val:f32,
+) {
+    let last_dir=if last_val>0.0{1}else if last_val<0.0{-1}else{0};
+    let cur_dir=if cur_val>0.0{1}else if cur_val<0.0{-1}else{0};
+
+    if last_val!=cur_val {
+        mapping_events.push(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: cur_val, owner });
+    }
+
+    if cur_dir!=last_dir {
+        if last_dir!=0 {
+            mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
+        }
+
+        if cur_dir!=0 {
+            mapping_events.push(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: cur_dir, owner });
+        }
+    }
+}
+
+//on a binding group's primary release, (held_time,dir,fired) from its interaction_times
+fn send_interaction_release<M: Send + Sync + 'static + Clone + Debug>(
+    mapping_events:&mut Vec<InputMapMessage<M>>,
+    mapping:&M, owner:i32, interaction:BindingInteraction, (held_time,dir,fired):(f32,i32,bool),
+) {
+    match interaction {
+        BindingInteraction::Tap(secs) if held_time<secs => {
+            mapping_events.push(InputMapMessage::Tap { mapping: mapping.clone(), dir, owner });
+        }
+        BindingInteraction::LongPress(_) if !fired => {
+            mapping_events.push(InputMapMessage::LongPress { mapping: mapping.clone(), dir, progress: 0.0, owner });
+        }
+        BindingInteraction::PressRelease => {
+            mapping_events.push(InputMapMessage::PressReleased { mapping: mapping.clone(), dir, owner });
+        }
+        _ => {}
+    }
+}
+
+//on a mapping's JustPressed, count it as a tap if within max_interval of the last one in the same dir
+fn multi_tap_press<M: Send + Sync + 'static + Clone + Debug>(
+    mapping_events:&mut Vec<InputMapMessage<M>>,
+    mapping_val:&mut MappingVal,
+    mapping:&M, owner:i32, dir:i32, max_interval:f32,
+) {
+    if dir==mapping_val.tap_dir && mapping_val.tap_time_accum<=max_interval {
+        mapping_val.tap_count+=1;
+    } else {
+        mapping_val.tap_count=1;
+    }
+
+    mapping_val.tap_dir=dir;
+    mapping_val.tap_time_accum=0.0;
+
+    if mapping_val.tap_count>=2 {
+        mapping_events.push(InputMapMessage::MultiTap { mapping: mapping.clone(), dir, count: mapping_val.tap_count, owner });
+    }
+}
+
+fn scroll_pixel_steps(accum:&mut f32,value:f32,pixels_per_line:f32) -> f32 {
+    //restart accumulating when scroll direction changes
+    if *accum*value<0.0 {
+        *accum=0.0;
+    }
+
+    *accum+=value;
+
+    let steps=(*accum/pixels_per_line).trunc();
+    *accum-=steps*pixels_per_line;
+    steps
+}
+
+//the leftovers are dropped when pixels_per_line changes or after reset_secs without scrolling, as a wheel doesn't have any
+fn scroll_pixel_lines(accum:&mut ScrollPixelAccum,x:f32,y:f32,pixels_per_line:f32,reset_secs:f32,cur_time:f32) -> (f32,f32) {
+    if accum.pixels_per_line!=pixels_per_line || cur_time-accum.time>reset_secs {
+        *accum=ScrollPixelAccum { pixels_per_line, ..Default::default() };
+    }
+
+    accum.time=cur_time;
+
+    (
+        scroll_pixel_steps(&mut accum.x,x,pixels_per_line),
+        scroll_pixel_steps(&mut accum.y,y,pixels_per_line),
+    )
+}
+
+//the device a binding is from, for keyboard/mouse a chord's modifiers and primary can be on different devices
+fn binding_device(device:Device,binding:Binding) -> Device {
+    if let Device::Gamepad(_)=device {
+        return device;
+    }
+
+    match binding.device_kind() {
+        Some(DeviceKind::Keyboard) => Device::Keyboard,
+        Some(DeviceKind::Mouse) => Device::Mouse,
+        Some(DeviceKind::Touch) => Device::Touch,
+        _ => device,
+    }
+}
+
+fn device_owners(
+    device:Device,
+    device_owner:&HashMap<Device,i32>,
+    device_kind_owners:&HashMap<DeviceKind,HashSet<i32>>,
+) -> Vec<i32> {
+    if let Device::Gamepad(_)=device {
+        device_owner.get(&device).cloned().into_iter().collect()
+    } else {
+        device_kind_owners.get(&device.kind()).map(|owners|owners.iter().cloned().collect()).unwrap_or_default()
+    }
+}
+
+//the binding with the Key/LogicalKey sent by the same key press, the pair is only known once the key's label is
+fn same_key_bindings(binding:Binding,key_labels:&HashMap<KeyCode,String>) -> Vec<Binding> {
+    let logical_key=|key_code|key_labels.get(&key_code).and_then(|label|label.to_lowercase().chars().next());
+
+    match binding {
+        Binding::Key(key_code) => {
+            [binding].into_iter().chain(logical_key(key_code).map(Binding::LogicalKey)).collect()
+        }
+        Binding::LogicalKey(c) => {
+            [binding].into_iter().chain(key_labels.keys().filter(|&&key_code|logical_key(key_code)==Some(c)).map(|&key_code|Binding::Key(key_code))).collect()
+        }
+        _ => vec![binding],
+    }
+}
+
+//when kbm is split, the owner a kbm binding is sent to, ie the lowest owner that has it (or its Key/LogicalKey pair) bound
+fn kbm_split_owner(
+    binding:Binding,
+    kbm_binding_owners:&HashMap<Binding,Vec<i32>>,
+    kind_owners:&HashMap<DeviceKind,HashSet<i32>>,
+    key_labels:&HashMap<KeyCode,String>,
+) -> Option<i32> {
+    let kind_owners=binding.device_kind().and_then(|kind|kind_owners.get(&kind))?;
+
+    same_key_bindings(binding,key_labels).iter()
+        .filter_map(|binding|kbm_binding_owners.get(binding))
+        .flat_map(|binding_owners|binding_owners.iter())
+        .filter(|owner|kind_owners.contains(owner))
+        .min().cloned()
+}
+
+//keyboard and mouse share a bind chain when both are in bind mode, so eg ctrl+click can be bound,
+//  the bind messages still have the device of the input
+fn bind_chain_device(device:Device,bind_mode_devices:&HashSet<Device>) -> Device {
+    if device==Device::Mouse && bind_mode_devices.contains(&Device::Keyboard) {
+        Device::Keyboard
+    } else {
+        device
+    }
+}
+
 fn is_binding_bind_mode(
     // bind_mode : bool,
     // owner : Option<i32>,
@@ -88,13 +264,19 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
     mut mouse_move_events: MessageReader<bevy::input::mouse::MouseMotion>,
     mut mouse_scroll_events: MessageReader<bevy::input::mouse::MouseWheel>,
     mut mouse_button_events : MessageReader<bevy::input::mouse::MouseButtonInput>,
+    mut keyboard_focus_lost_events : MessageReader<bevy::input::keyboard::KeyboardFocusLost>,
 
     mut gamepad_axis_lasts : Local<HashMap<(Device,GamepadAxis),f32>>,
     mut key_lasts : Local<HashSet<KeyCode>>,
+    mut logical_key_lasts : Local<HashMap<KeyCode,char>>, //[key]=logical_key
+    mut mouse_button_lasts : Local<HashSet<MouseButton>>,
+    mut scroll_pixel_accum : Local<ScrollPixelAccum>,
 
     mut binding_input_event_writer: MessageWriter<BindingInputMessage>,
 
-    gamepad_dead_zones_query: Query<& GamepadDeadZone>,
+    mut input_map : ResMut<InputMap<M>>,
+    gamepad_query: Query<(Option<&GamepadDeadZone>,Option<&GamepadOwner>)>,
+    time: Res<bevy::time::Time<bevy::time::Real>>,
 ) {
     //
     for event in gamepad_events.read() {
@@ -106,7 +288,7 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
                 let entity=*entity;
                 let device=Device::Gamepad(entity);
                 let binding=Binding::GamepadButton(*button_type);
-                let dead_zone=gamepad_dead_zones_query.get(entity).ok().and_then(|dead_zones|dead_zones.0.get(&binding));
+                let (dead_zone,_)=gamepad_settings(entity,binding,&gamepad_query,&input_map.owner_gamepad_settings);
                 let value=use_dead_zone(*value,dead_zone);
 
                 binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
@@ -116,8 +298,17 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
                 let axis_type=*axis_type;
                 let device=Device::Gamepad(entity);
                 let binding=Binding::GamepadAxis(axis_type);
-                let dead_zone=gamepad_dead_zones_query.get(entity).ok().and_then(|dead_zones|dead_zones.0.get(&binding));
-                let value=use_dead_zone(*value,dead_zone);
+                let (dead_zone,owner_settings)=gamepad_settings(entity,binding,&gamepad_query,&input_map.owner_gamepad_settings);
+                let mut value=use_dead_zone(*value,dead_zone);
+
+                if let Some(owner_settings)=owner_settings {
+                    if owner_settings.inverts.contains(&axis_type) {
+                        value*=-1.0;
+                    }
+
+                    value*=owner_settings.sensitivities.get(&axis_type).cloned().unwrap_or(1.0);
+                }
+
                 let last_value=gamepad_axis_lasts.get(&(device,axis_type)).cloned().unwrap_or_default();
 
                 binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
@@ -145,22 +336,49 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
     for ev in key_events.read() {
         let immediate=false;
 
+        //lowercase so shift etc don't change which logical key binding is used,
+        //  but only for letters, shifted symbols are sent as is (eg '!' instead of '1') as bevy doesn't give the unshifted char
+        let logical_key=if let bevy::input::keyboard::Key::Character(s)=&ev.logical_key {
+            let mut chars=s.chars();
+            chars.next().filter(|_|chars.next().is_none()).and_then(|c|c.to_lowercase().next())
+        } else {
+            None
+        };
+
         match ev.state {
             bevy::input::ButtonState::Pressed => { //repeats
                 if !key_lasts.contains(&ev.key_code) { //don't send if just a repeat
-                    let device=Device::Other;
+                    let device=Device::Keyboard;
                     let binding=Binding::Key(ev.key_code);
                     let value=1.0;
                     binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
                     key_lasts.insert(ev.key_code);
+
+                    if let Some(logical_key)=logical_key {
+                        let binding=Binding::LogicalKey(logical_key);
+                        binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+                        logical_key_lasts.insert(ev.key_code,logical_key);
+
+                        let label=logical_key.to_uppercase().to_string();
+
+                        if input_map.key_labels.get(&ev.key_code)!=Some(&label) {
+                            input_map.key_labels.insert(ev.key_code,label);
+                        }
+                    }
                 }
             }
             bevy::input::ButtonState::Released => { //once
-                let device=Device::Other;
+                let device=Device::Keyboard;
                 let value=0.0;
                 let binding=Binding::Key(ev.key_code);
                 binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
                 key_lasts.remove(&ev.key_code); //may not exist, if there was somehow a release without a press
+
+                //release the logical key that was pressed, since the layout's char can differ on release eg shift released first
+                if let Some(logical_key)=logical_key_lasts.remove(&ev.key_code) {
+                    let binding=Binding::LogicalKey(logical_key);
+                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+                }
             }
         }
     }
@@ -171,24 +389,51 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
 
         match ev.state {
             bevy::input::ButtonState::Pressed => {
-                let device=Device::Other;
+                let device=Device::Mouse;
                 let binding=Binding::MouseButton(ev.button);
                 let value=1.0;
                 binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+                mouse_button_lasts.insert(ev.button);
             }
             bevy::input::ButtonState::Released => {
-                let device=Device::Other;
+                let device=Device::Mouse;
                 let binding=Binding::MouseButton(ev.button);
                 let value=0.0;
                 binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+                mouse_button_lasts.remove(&ev.button);
             }
         }
     }
 
+    //release held keys and mouse buttons when the window loses focus, since their release events won't be received
+    if keyboard_focus_lost_events.read().count()>0 {
+        let immediate=false;
+        let value=0.0;
+
+        let device=Device::Keyboard;
+
+        for key_code in key_lasts.drain() {
+            let binding=Binding::Key(key_code);
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+
+        for (_,logical_key) in logical_key_lasts.drain() {
+            let binding=Binding::LogicalKey(logical_key);
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+
+        let device=Device::Mouse;
+
+        for button in mouse_button_lasts.drain() {
+            let binding=Binding::MouseButton(button);
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+    }
+
     //
     for ev in mouse_move_events.read() {
         let immediate=true;
-        let device=Device::Other;
+        let device=Device::Mouse;
 
         if ev.delta.x!=0.0 {
             let binding=Binding::MouseMoveX;
@@ -226,42 +471,10 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
     //
     for ev in mouse_scroll_events.read() {
         let immediate=true;
-        let device=Device::Other;
+        let device=Device::Mouse;
 
-        match ev.unit {
-            bevy::input::mouse::MouseScrollUnit::Line => {
-                if ev.x!=0.0 {
-                    let binding=Binding::MouseScrollLineX;
-                    let value=ev.x;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-                if ev.x>0.0 {
-                    let binding=Binding::MouseScrollLinePosX;
-                    let value=ev.x;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-                if ev.x<0.0 {
-                    let binding=Binding::MouseScrollLineNegX;
-                    let value=ev.x;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-
-                if ev.y!=0.0 {
-                    let binding=Binding::MouseScrollLineY;
-                    let value=ev.y;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-                if ev.y>0.0 {
-                    let binding=Binding::MouseScrollLinePosY;
-                    let value=ev.y;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-                if ev.y<0.0 {
-                    let binding=Binding::MouseScrollLineNegY;
-                    let value=ev.y;
-                    binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
-                }
-            }
+        let (line_x,line_y)=match ev.unit {
+            bevy::input::mouse::MouseScrollUnit::Line => (ev.x,ev.y),
             bevy::input::mouse::MouseScrollUnit::Pixel => {
                 // println!("!==w3erfdsfdsfds");
                 if ev.x!=0.0 {
@@ -295,7 +508,46 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
                     let value=ev.y;
                     binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
                 }
+
+                //also send pixel scrolls as line scrolls, every scroll_pixels_per_line pixels
+                if input_map.scroll_pixels_per_line>0.0 {
+                    scroll_pixel_lines(&mut scroll_pixel_accum,ev.x,ev.y,input_map.scroll_pixels_per_line,input_map.scroll_pixels_reset_secs,time.elapsed_secs())
+                } else {
+                    (0.0,0.0)
+                }
             }
+        };
+
+        if line_x!=0.0 {
+            let binding=Binding::MouseScrollLineX;
+            let value=line_x;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+        if line_x>0.0 {
+            let binding=Binding::MouseScrollLinePosX;
+            let value=line_x;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+        if line_x<0.0 {
+            let binding=Binding::MouseScrollLineNegX;
+            let value=line_x;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+
+        if line_y!=0.0 {
+            let binding=Binding::MouseScrollLineY;
+            let value=line_y;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+        if line_y>0.0 {
+            let binding=Binding::MouseScrollLinePosY;
+            let value=line_y;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
+        }
+        if line_y<0.0 {
+            let binding=Binding::MouseScrollLineNegY;
+            let value=line_y;
+            binding_input_event_writer.write(BindingInputMessage { device, immediate, binding, value, });
         }
     }
 
@@ -304,6 +556,489 @@ pub fn binding_inputs_system<M: Send + Sync + 'static + Eq + Debug> (
 
 
 
+pub fn owner_entity_system<M: Send + Sync + 'static + Eq + Hash + Clone> (
+    mut commands: Commands,
+    mut input_map : ResMut<InputMap<M>>,
+    owner_bindings_query: Query<(Entity,Ref<OwnerBindings<M>>)>,
+    gamepad_query: Query<(Entity,&GamepadOwnerEntity,Option<&GamepadOwner>),With<Gamepad>>,
+    gamepad_owner_query: Query<&GamepadOwner,Without<GamepadOwnerEntity>>,
+    mut removed_owner_bindings: RemovedComponents<OwnerBindings<M>>,
+    mut removed_gamepad_owner_entities: RemovedComponents<GamepadOwnerEntity>,
+) {
+    let input_map=input_map.as_mut();
+
+    //remove owners of entities despawned or with OwnerBindings removed
+    for entity in removed_owner_bindings.read() {
+        if owner_bindings_query.contains(entity) { //readded
+            continue;
+        }
+
+        let Some(owner)=input_map.entity_owner(entity) else {continue;};
+
+        input_map.owner_entities.remove(&owner);
+        input_map.owner_bindings.remove(&owner);
+        input_map.bindings_updated=true;
+
+        if let Ok(mut entity_commands)=commands.get_entity(entity) {
+            entity_commands.remove::<InputOwner>();
+        }
+    }
+
+    //give owners to new entities, and update owner bindings from changed ones
+    for (entity,bindings) in owner_bindings_query.iter() {
+        let owner=match input_map.entity_owner(entity) {
+            Some(_) if !bindings.is_changed() => { continue; }
+            Some(owner) => owner,
+            None => {
+                //not an owner with bindings, or one held by another entity, the kbm or a gamepad (eg joined)
+                let mut taken_owners=input_map.reserved_owners();
+                taken_owners.extend(input_map.owner_bindings.keys().cloned());
+                taken_owners.extend(gamepad_owner_query.iter().map(|gamepad_owner|gamepad_owner.0));
+
+                let owner=(0..).find(|owner|!taken_owners.contains(owner)).unwrap();
+                input_map.owner_entities.insert(owner,entity);
+                commands.entity(entity).insert(InputOwner(owner));
+                owner
+            }
+        };
+
+        input_map.owner_bindings.insert(owner,bindings.0.clone());
+        input_map.bindings_updated=true;
+    }
+
+    //gamepad owners from owner entities
+    for entity in removed_gamepad_owner_entities.read() {
+        if gamepad_query.contains(entity) { //readded
+            continue;
+        }
+
+        if let Ok(mut entity_commands)=commands.get_entity(entity) {
+            entity_commands.remove::<GamepadOwner>();
+        }
+    }
+
+    for (entity,owner_entity,gamepad_owner) in gamepad_query.iter() {
+        match (input_map.entity_owner(owner_entity.0),gamepad_owner) {
+            (Some(owner),Some(gamepad_owner)) if owner==gamepad_owner.0 => {}
+            (Some(owner),_) => {
+                commands.entity(entity).insert(GamepadOwner(owner));
+            }
+            (None,Some(_)) => {
+                commands.entity(entity).remove::<GamepadOwner>();
+            }
+            (None,None) => {}
+        }
+    }
+}
+
+pub fn owner_action_state_system<M: Send + Sync + 'static + Eq + Hash + Clone + Debug> (
+    input_map : Res<InputMap<M>>,
+    mut input_map_events: MessageReader<InputMapMessage<M>>,
+    mut action_state_query: Query<&mut OwnerActionState<M>>,
+) {
+    for event in input_map_events.read() {
+        let owner=match event {
+            InputMapMessage::ValueChanged { owner, .. }
+                |InputMapMessage::JustPressed { owner, .. }
+                |InputMapMessage::JustReleased { owner, .. } => *owner,
+            _ => { continue; }
+        };
+
+        let Some(&entity)=input_map.owner_entities.get(&owner) else {continue;};
+        let Ok(mut action_state)=action_state_query.get_mut(entity) else {continue;};
+
+        match event {
+            InputMapMessage::ValueChanged { mapping, val, .. } if *val==0.0 => {
+                action_state.values.remove(mapping);
+            }
+            InputMapMessage::ValueChanged { mapping, val, .. } => {
+                action_state.values.insert(mapping.clone(),*val);
+            }
+            InputMapMessage::JustPressed { mapping, dir, .. } => {
+                action_state.pressed.insert(mapping.clone(),*dir);
+            }
+            InputMapMessage::JustReleased { mapping, dir, .. } if action_state.pressed.get(mapping)==Some(dir) => {
+                action_state.pressed.remove(mapping);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn input_buffer_system<M: Send + Sync + 'static + Eq + Hash + Clone + Debug> (
+    mut input_map : ResMut<InputMap<M>>,
+    time: Res<bevy::time::Time>,
+    mut input_map_events: MessageReader<InputMapMessage<M>>,
+) {
+    let InputMap { mapping_buffers, buffered_presses, .. }=input_map.as_mut();
+
+    //expire
+    buffered_presses.retain(|_,(_,remaining)|{
+        match remaining {
+            InputBuffer::Secs(secs) => {
+                *secs-=time.delta_secs();
+                *secs>0.0
+            }
+            InputBuffer::Frames(frames) => {
+                *frames=frames.saturating_sub(1);
+                *frames>0
+            }
+        }
+    });
+
+    //buffer presses
+    for event in input_map_events.read() {
+        let InputMapMessage::JustPressed { mapping, dir, owner }=event else {continue;};
+        let Some(&buffer)=mapping_buffers.get(mapping) else {continue;};
+        buffered_presses.insert((*owner,mapping.clone()),(*dir,buffer));
+    }
+}
+
+type SequenceHistory<M> = Vec<(f32,HashMap<M,i32>)>; //[(time,dirs)]
+
+fn is_step_held<M: Eq + Hash>(step:&[(M,i32)],dirs:&HashMap<M,i32>) -> bool {
+    step.iter().all(|(mapping,dir)|dirs.get(mapping)==Some(dir))
+}
+
+pub fn sequence_system<M: Send + Sync + 'static + Eq + Hash + Clone + Debug> (
+    input_map : Res<InputMap<M>>,
+    time: Res<bevy::time::Time>,
+    mut input_map_messages: ResMut<bevy::ecs::message::Messages<InputMapMessage<M>>>, //reads and writes them, so can't use MessageReader/MessageWriter
+    mut input_map_cursor: Local<bevy::ecs::message::MessageCursor<InputMapMessage<M>>>,
+    mut owner_dirs : Local<HashMap<i32,HashMap<M,i32>>>, //[owner][mapping]=dir, of pressed
+    mut owner_histories : Local<HashMap<i32,SequenceHistory<M>>>, //[owner]=history
+) {
+    let cur_time=time.elapsed_secs();
+    let max_duration=input_map.sequences.values().map(|sequence|sequence.max_duration).fold(0.0,f32::max);
+
+    for history in owner_histories.values_mut() {
+        history.retain(|&(history_time,_)|cur_time-history_time<=max_duration);
+    }
+
+    let messages=input_map_cursor.read(&input_map_messages).cloned().collect::<Vec<_>>();
+    let mut completeds=Vec::new();
+
+    for message in messages {
+        let owner=match message {
+            InputMapMessage::JustPressed { mapping, dir, owner } => {
+                owner_dirs.entry(owner).or_default().insert(mapping,dir);
+                owner
+            }
+            InputMapMessage::JustReleased { mapping, dir, owner } => {
+                let dirs=owner_dirs.entry(owner).or_default();
+
+                if dirs.get(&mapping)==Some(&dir) {
+                    dirs.remove(&mapping);
+                }
+
+                owner
+            }
+            _ => { continue; }
+        };
+
+        if input_map.sequences.is_empty() {
+            continue;
+        }
+
+        let dirs=owner_dirs.get(&owner).cloned().unwrap_or_default();
+        let history=owner_histories.entry(owner).or_default();
+        let last_dirs=history.last().map(|(_,last_dirs)|last_dirs);
+
+        for (sequence_mapping,sequence) in input_map.sequences.iter() {
+            //last step just held
+            let Some(last_step)=sequence.steps.last() else {continue;};
+
+            if !is_step_held(last_step,&dirs) || last_dirs.map(|last_dirs|is_step_held(last_step,last_dirs)).unwrap_or_default() {
+                continue;
+            }
+
+            //find the other steps, most recent first
+            let mut step_time=cur_time;
+            let mut history_len=history.len();
+            let mut found=true;
+
+            for step in sequence.steps.iter().rev().skip(1) {
+                let Some(index)=history[..history_len].iter().rposition(|(_,history_dirs)|is_step_held(step,history_dirs)) else {
+                    found=false;
+                    break;
+                };
+
+                let history_time=history[index].0;
+
+                if step_time-history_time>sequence.step_leniency || cur_time-history_time>sequence.max_duration {
+                    found=false;
+                    break;
+                }
+
+                step_time=history_time;
+                history_len=index;
+            }
+
+            if found {
+                completeds.push(InputMapMessage::SequenceCompleted { mapping: sequence_mapping.clone(), owner });
+            }
+        }
+
+        history.push((cur_time,dirs));
+    }
+
+    for completed in completeds {
+        input_map_messages.write(completed);
+    }
+}
+
+type GamepadAxisLasts = HashMap<(Entity,GamepadAxis),f32>;
+
+pub fn gamepad_calibration_system<M: Send + Sync + 'static + Eq + Debug> (
+    mut commands: Commands,
+    mut axis_events: MessageReader<GamepadAxisChangedEvent>, //after bevy's AxisSettings, same values the dead zones are applied to
+    mut input_map_event: MessageWriter<InputMapMessage<M>>,
+    time: Res<bevy::time::Time<bevy::time::Real>>,
+    calibration_query: Query<(Entity,&GamepadCalibration,Option<&GamepadDeadZone>),With<Gamepad>>,
+    (mut axis_lasts,mut calibration_states) : (Local<GamepadAxisLasts>,Local<HashMap<Entity,CalibrationState>>), //bevy only sends axis events on change, so keep the lasts
+) {
+    let axes=[
+        GamepadAxis::LeftStickX, GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX, GamepadAxis::RightStickY,
+        GamepadAxis::LeftZ, GamepadAxis::RightZ,
+    ];
+
+    let cur_time=time.elapsed_secs();
+
+    for event in axis_events.read() {
+        axis_lasts.insert((event.entity,event.axis),event.value);
+    }
+
+    //calibrations removed or gamepads disconnected
+    calibration_states.retain(|&entity,_|calibration_query.contains(entity));
+
+    for (entity,calibration,dead_zone) in calibration_query.iter() {
+        let state=calibration_states.entry(entity).or_insert_with(||CalibrationState {
+            start_time: cur_time, rest_ranges: Default::default(), full_ranges: Default::default(),
+        });
+
+        let elapsed=cur_time-state.start_time;
+        let total_secs=calibration.rest_secs+calibration.range_secs;
+
+        //sample
+        let ranges=if elapsed < calibration.rest_secs {&mut state.rest_ranges} else {&mut state.full_ranges};
+
+        for axis in axes {
+            let value=axis_lasts.get(&(entity,axis)).cloned().unwrap_or_default();
+            let (min,max)=ranges.entry(axis).or_insert((value,value));
+            *min=min.min(value);
+            *max=max.max(value);
+        }
+
+        if elapsed < total_secs {
+            input_map_event.write(InputMapMessage::CalibrationProgress { entity, progress: (elapsed/total_secs).max(0.0) });
+            continue;
+        }
+
+        //calc dead zones
+        let mut dead_zones=HashMap::new();
+
+        for axis in axes {
+            let (rest_min,rest_max)=state.rest_ranges.get(&axis).cloned().unwrap_or_default();
+            let (full_min,full_max)=state.full_ranges.get(&axis).cloned().unwrap_or_default();
+
+            let center=(rest_min+rest_max)*0.5;
+            let noise=(rest_max-rest_min)*0.5+calibration.margin;
+
+            //use the default extents if the full range wasn't reached past the noise
+            let pos_max=if full_max-center > noise {full_max-center} else {1.0-center};
+            let neg_max=if full_min-center < -noise {full_min-center} else {-1.0-center};
+
+            dead_zones.insert(Binding::GamepadAxis(axis),DeadZone { center, pos_min: noise, pos_max, neg_min: -noise, neg_max });
+        }
+
+        //keep the gamepad's other dead zones, eg buttons
+        let mut merged_dead_zones=dead_zone.map(|dead_zone|dead_zone.0.clone()).unwrap_or_default();
+        merged_dead_zones.extend(dead_zones.clone());
+
+        calibration_states.remove(&entity);
+        commands.entity(entity).remove::<GamepadCalibration>().insert(GamepadDeadZone(merged_dead_zones));
+        input_map_event.write(InputMapMessage::CalibrationProgress { entity, progress: 1.0 });
+        input_map_event.write(InputMapMessage::CalibrationFinished { entity, dead_zones });
+    }
+}
+
+type GamepadConnectionQuery<'w,'s> = Query<'w,'s,(Option<&'static GamepadOwner>,Option<&'static GamepadDeadZone>,Option<&'static GamepadBindMode>,Has<Gamepad>)>;
+
+pub fn gamepad_connection_system<M: Send + Sync + 'static + Eq + Debug> (
+    mut commands: Commands,
+    mut connection_events: MessageReader<GamepadConnectionEvent>,
+    mut input_map_event: MessageWriter<InputMapMessage<M>>,
+    input_map : Res<InputMap<M>>,
+    time: Res<bevy::time::Time<bevy::time::Real>>, //real, so grace still elapses when the game is paused on disconnect
+    gamepad_query: GamepadConnectionQuery,
+    (mut gamepad_infos,mut disconnecteds) : (Local<HashMap<Entity,GamepadInfo>>,Local<Vec<DisconnectedGamepad>>),
+) {
+    let cur_time=time.elapsed_secs();
+    disconnecteds.retain(|disconnected|cur_time-disconnected.time <= input_map.gamepad_reconnect_grace);
+
+    let mut taken_owners=gamepad_query.iter()
+        .filter_map(|(owner,_,_,connected)|owner.filter(|_|connected).map(|owner|owner.0))
+        .collect::<HashSet<_>>();
+
+    for event in connection_events.read() {
+        let entity=event.gamepad;
+
+        match &event.connection {
+            GamepadConnection::Connected { name, vendor_id, product_id } => {
+                let info=GamepadInfo { name: name.clone(), vendor_id: *vendor_id, product_id: *product_id };
+                gamepad_infos.insert(entity,info.clone());
+
+                let mut owner=gamepad_query.get(entity).ok().and_then(|(owner,..)|owner).map(|owner|owner.0);
+
+                //give back owner etc of the same gamepad, if its owner isn't taken
+                let index=owner.is_none().then(||disconnecteds.iter().rposition(|disconnected|{
+                    disconnected.info==info && !taken_owners.contains(&disconnected.owner)
+                })).flatten();
+
+                if let Some(index)=index {
+                    let disconnected=disconnecteds.remove(index);
+                    let mut entity_commands=commands.entity(entity);
+                    entity_commands.insert(GamepadOwner(disconnected.owner));
+
+                    //the same entity still has them, so only for a different one
+                    let (has_dead_zone,has_bind_mode)=gamepad_query.get(entity).map(|(_,dead_zone,bind_mode,_)|(dead_zone.is_some(),bind_mode.is_some())).unwrap_or_default();
+
+                    if let Some(dead_zone)=disconnected.dead_zone.filter(|_|!has_dead_zone) {
+                        entity_commands.insert(dead_zone);
+                    }
+
+                    if let Some(bind_mode)=disconnected.bind_mode.filter(|_|!has_bind_mode) {
+                        entity_commands.insert(bind_mode);
+                    }
+
+                    taken_owners.insert(disconnected.owner);
+                    owner=Some(disconnected.owner);
+                }
+
+                input_map_event.write(InputMapMessage::GamepadConnect {
+                    entity, owner, name: info.name, vendor_id: info.vendor_id, product_id: info.product_id,
+                });
+            }
+            GamepadConnection::Disconnected => {
+                let Some(info)=gamepad_infos.remove(&entity) else {continue;};
+                let Ok((owner,dead_zone,bind_mode,_))=gamepad_query.get(entity) else {continue;};
+                let owner=owner.map(|owner|owner.0);
+
+                if let Some(owner)=owner {
+                    disconnecteds.push(DisconnectedGamepad {
+                        info: info.clone(),
+                        owner,
+                        dead_zone: dead_zone.cloned(),
+                        bind_mode: bind_mode.cloned(),
+                        time: cur_time,
+                    });
+
+                    //entity is kept by bevy, so remove so it can't end up sharing the owner if reconnected as a different entity,
+                    //  its dead zone and bind mode are kept, as they're for the device
+                    if let Ok(mut entity_commands)=commands.get_entity(entity) {
+                        entity_commands.remove::<GamepadOwner>();
+                    }
+                }
+
+                input_map_event.write(InputMapMessage::GamepadDisconnect {
+                    entity, owner, name: info.name, vendor_id: info.vendor_id, product_id: info.product_id,
+                });
+            }
+        }
+    }
+}
+
+type JoinBindings<'a> = Vec<(&'a Vec<Binding>,f32,f32)>; //[(bindings,primary_dead,modifier_dead)]
+
+pub fn gamepad_join_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt::Debug> (
+    mut commands: Commands,
+    mut binding_input_events: MessageReader<BindingInputMessage>,
+    mut mapping_event_writer: MessageWriter<InputMapMessage<M>>,
+    input_map : Res<InputMap<M>>,
+    gamepad_query: Query<(Entity,Option<& GamepadOwner>),With<Gamepad>>,
+    mut gamepad_binding_vals : Local<HashMap<(Entity,Binding),f32>>, //zero vals are not stored
+) {
+    let mut gamepad_owners = gamepad_query.iter().map(|(entity,owner)|(entity,owner.map(|x|x.0))).collect::<HashMap<_,_>>();
+    gamepad_binding_vals.retain(|(entity,_),_|gamepad_owners.contains_key(entity));
+
+    let mut join_bindings: JoinBindings = Vec::new();
+    let mut owner_leave_bindings: HashMap<i32,JoinBindings> = HashMap::new();
+
+    for (&owner,mappings) in input_map.owner_bindings.iter() {
+        for ((mapping,bindings),&(_scale,primary_dead,modifier_dead)) in mappings.iter() {
+            if bindings.is_empty() || !bindings.iter().all(|binding|binding.is_gamepad_device()) {
+                continue;
+            }
+
+            if Some(mapping)==input_map.join_mapping.as_ref() {
+                join_bindings.push((bindings,primary_dead,modifier_dead));
+            }
+
+            if Some(mapping)==input_map.leave_mapping.as_ref() {
+                owner_leave_bindings.entry(owner).or_default().push((bindings,primary_dead,modifier_dead));
+            }
+        }
+    }
+
+    //
+    for binding_input in binding_input_events.read() {
+        let Device::Gamepad(entity)=binding_input.device else {continue;};
+        let Some(&owner)=gamepad_owners.get(&entity) else {continue;};
+
+        let last_val=gamepad_binding_vals.get(&(entity,binding_input.binding)).cloned().unwrap_or_default();
+
+        if binding_input.value==0.0 {
+            gamepad_binding_vals.remove(&(entity,binding_input.binding));
+        } else {
+            gamepad_binding_vals.insert((entity,binding_input.binding),binding_input.value);
+        }
+
+        //chord's primary just pressed and modifiers held, using the bindings' dead zones like mapping_event_system
+        let is_held=|val:f32,dead:f32|val!=0.0 && val.abs()>=dead;
+
+        let is_pressed=|&(bindings,primary_dead,modifier_dead):&(&Vec<Binding>,f32,f32)|{
+            let (&primary,modifiers)=bindings.split_last().unwrap();
+
+            primary==binding_input.binding && !is_held(last_val,primary_dead) && is_held(binding_input.value,primary_dead)
+                && modifiers.iter().all(|&modifier|{
+                    is_held(gamepad_binding_vals.get(&(entity,modifier)).cloned().unwrap_or_default(),modifier_dead)
+                })
+        };
+
+        if let Some(owner)=owner {
+            let Some(leave_bindings)=owner_leave_bindings.get(&owner) else {continue;};
+
+            if leave_bindings.iter().any(is_pressed) {
+                commands.entity(entity).remove::<GamepadOwner>();
+                gamepad_owners.insert(entity,None);
+                mapping_event_writer.write(InputMapMessage::OwnerLeft { owner, entity });
+            }
+        } else if join_bindings.iter().any(is_pressed) {
+            //owners of other gamepads, entities and the kbm
+            let mut taken_owners=input_map.reserved_owners();
+            taken_owners.extend(gamepad_owners.values().filter_map(|&owner|owner));
+
+            let Some(owner)=(0..input_map.join_max_owners).find(|owner|!taken_owners.contains(owner)) else {
+                continue;
+            };
+
+            commands.entity(entity).insert(GamepadOwner(owner));
+            gamepad_owners.insert(entity,Some(owner));
+            mapping_event_writer.write(InputMapMessage::OwnerJoined { owner, entity });
+        }
+    }
+}
+
+type KindOwners = HashMap<DeviceKind,HashSet<i32>>; //[device_kind][owner]
+
+type ModifierLocals<'s,M> = (
+    Local<'s,HashMap<(Device,Binding),f32>>, //not just modifier, all binding vals except for immediate ones, zero val are not stored
+    Local<'s,(HashMap<(Device,Binding),u64>,u64)>, //(press order of binding vals, next order)
+    Local<'s,HashMap<(i32,Device,M,BindingGroup),f32>>, //[owner,device,mapping,binding_group]=input_val, lone modifiers pressed, waiting for release
+    Local<'s,HashMap<(i32,Binding),bool>>, //[(owner,modifier)]=is_tap, held modifiers, not a tap if anything else was pressed while held
+);
+
 pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt::Debug> (
     mut gamepad_events: MessageReader<GamepadEvent>,
     mut binding_input_events: MessageReader<BindingInputMessage>,
@@ -320,28 +1055,53 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
 
     mut device_bind_mode_lasts : Local<HashSet<Device>>,
 
-    mut modifier_binding_vals : Local<HashMap<(Device,Binding),f32>>, //not just modifier, all binding vals except for immediate ones, zero val are not stored
+    (mut modifier_binding_vals,mut binding_press_orders,mut lone_modifier_pendings,mut sticky_tap_modifiers) : ModifierLocals<M>,
 
     mut owner_mappings : Local<HashMap<i32, HashMap<M,MappingVal>>>, //[player][mapping]=mapping_val
     mut owner_primary_mappings : Local<HashMap<i32, HashMap<Binding,HashSet<(M,BindingGroup)>>>>, //[player][primary_binding][(mapping,binding_group)]
     mut owner_modifier_mappings : Local<HashMap<i32, HashMap<Binding,HashSet<(M,BindingGroup)>>>>, //[player][modifier_binding][(mapping,binding_group)]
 
 
-    mut other_device_owners : Local<HashSet<i32>>,
+    mut kbm_binding_owners : Local<HashMap<Binding,Vec<i32>>>, //[kbm_binding][owner], sorted owners
+
+    (mut device_kind_owners,mut device_kind_last_owners) : (Local<KindOwners>,Local<KindOwners>), //for non gamepad devices, (from bindings, last used)
 ) {
     let InputMap {
         owner_bindings, bindings_updated: owner_bindings_updated,
         mapping_repeats,
+        mapping_multi_taps,
+        ordered_chords,
+        lone_modifiers_on_release,
+        owner_sticky_modifiers,
+        owner_latched_modifiers,
+        binding_interactions,
+        chord_resolution,
+        shared_mappings,
         // bind_mode_owner_includes,
         // bind_mode_owner_excludes,
         bind_mode_includes,
         bind_mode_excludes,
         bind_mode_start_dead,bind_mode_end_dead,
-        // kbm_owner,
-        kbm_bind_mode: bind_mode_kbm,
+        bind_mode_logical_keys,
+        kbm_owner,
+        kbm_split,
+        key_labels,
+        keyboard_bind_mode,
+        mouse_bind_mode,
+        owner_active_devices,
+        active_device_threshold,
+        active_device_mouse_move_threshold,
+        owner_kbm_mapping_inverts,
+        owner_gamepad_mapping_inverts,
+        owner_toggle_mappings,
+        owner_toggle_vals,
         ..
     }=input_map.as_mut();
 
+    //sent at the end, after toggle mappings applied
+    let mut mapping_events: Vec<InputMapMessage<M>> = Vec::new();
+    let mut toggle_mode_events: Vec<InputMapMessage<M>> = Vec::new(); //sent without toggle mappings applied
+
     //store last mapping bindings sum val
     //slightly sucks to calculate this all the time,
     //  would prefer to do when needed for removes,
@@ -355,9 +1115,31 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
         }
     }
 
+    //toggle mappings turned on/off, go from the latched val to the bindings val, or vice versa
+    owner_toggle_vals.retain(|(owner,mapping),latched_val|{
+        if owner_toggle_mappings.contains(&(*owner,mapping.clone())) {
+            return true;
+        }
+
+        let last_val=owner_mapping_last_vals.get(&(*owner,mapping.clone())).cloned().unwrap_or_default();
+        send_val_changes(&mut toggle_mode_events,mapping,*owner,*latched_val,last_val);
+        false
+    });
+
+    for (owner,mapping) in owner_toggle_mappings.iter() {
+        let owner_mapping=(*owner,mapping.clone());
+
+        if owner_toggle_vals.contains_key(&owner_mapping) {
+            continue;
+        }
+
+        let last_val=owner_mapping_last_vals.get(&owner_mapping).cloned().unwrap_or_default();
+        send_val_changes(&mut toggle_mode_events,mapping,*owner,last_val,0.0);
+        owner_toggle_vals.insert(owner_mapping,0.0);
+    }
+
     //
     let mut device_owner = HashMap::new();
-    // device_owner.insert(Device::Other, *kbm_owner);
 
     for (entity,owner,_) in gamepad_query.iter() {
         let Some(owner)=owner.map(|x|x.0) else {continue;};
@@ -368,8 +1150,12 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
     //calc bind_mode_devices
     let mut bind_mode_devices:HashSet<Device> = HashSet::new();
 
-    if *bind_mode_kbm {
-        bind_mode_devices.insert(Device::Other);
+    if *keyboard_bind_mode {
+        bind_mode_devices.insert(Device::Keyboard);
+    }
+
+    if *mouse_bind_mode {
+        bind_mode_devices.insert(Device::Mouse);
     }
 
     for (entity,_,bind_mode) in gamepad_query.iter() {
@@ -385,72 +1171,6 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
     let mut owner_mapping_changeds  = HashSet::<(i32,Option<M>)>::new(); //[(owner,mapping]=changed
     // let mut device_removeds  = HashMap::<(Device, i32),bool>::new(); //[device,owner]=bind_mode_only
 
-    //handle bind_mode removes
-    //  clear presseds on bind mode
-    for &device in bind_mode_devices.iter() {
-        if !device_bind_mode_lasts.contains(&device) {
-            let owners=if let Device::Other=device {
-                other_device_owners.iter().cloned().collect::<Vec<_>>()
-            } else if let Some(&owner)=device_owner.get(&device) {
-                vec![owner]
-            } else {
-                Vec::new()
-            };
-
-            // let Some(&owner)=device_owner.get(&device) else {continue;};
-            // //check last owner is same, otherwise pointless?
-            // // device_removeds.insert((device,owner),true);
-
-            // // let bind_mode_excludes=bind_mode_owner_excludes.get(&owner);
-            // // let bind_mode_includes=bind_mode_owner_includes.get(&owner);
-
-
-            for owner in owners {
-                owner_mapping_changeds.insert((owner,None));
-
-                //
-                let Some(mapping_vals)=owner_mappings.get_mut(&owner) else { continue; };
-
-
-                //clear presseds on bind mode
-
-                for (_mapping,mapping_val) in mapping_vals.iter_mut() {
-                    //remove bind_groups that have device in bindmode, and aren't excluded from it
-                    mapping_val.binding_vals.retain(|(device2,bind_group),_|{
-                        let not_bind_mode=!bind_mode_devices.contains(device2);
-
-                        // let not_bind_mode=not_bind_mode || bind_mode_excludes.contains(&bind_group.primary);
-                        // let not_bind_mode=not_bind_mode || !is_binding_bind_mode(owner,&bind_mode_owner_excludes,&bind_mode_owner_includes,bind_group.primary);
-                        let not_bind_mode=not_bind_mode || !is_binding_bind_mode(&bind_mode_excludes,&bind_mode_includes,bind_group.primary);
-
-                        // let not_bind_mode=not_bind_mode || bind_group.modifiers.len()==bind_group.modifiers.iter().filter(|&&x|bind_mode_excludes.contains(x)).count();
-                        let not_bind_mode=not_bind_mode || bind_group.modifiers.len()==bind_group.modifiers.iter().filter(|&&binding|{
-                            // !is_binding_bind_mode(owner,&bind_mode_owner_excludes,&bind_mode_owner_includes,binding)
-                            !is_binding_bind_mode(&bind_mode_excludes,&bind_mode_includes,binding)
-                        }).count();
-
-                        not_bind_mode
-                    });
-                }
-            }
-
-        }
-    }
-
-    //need to handle kbm that has player removed from it
-    // {
-    //     let device=Device::Other;
-    //     let last_owner=device_prev_owners.get(&device).cloned().unwrap_or(0);
-    //     let cur_owner=*kbm_owner;
-
-    //     if last_owner!=cur_owner {
-    //         device_prev_owners.insert(device, cur_owner);
-    //         //check last owner is same, otherwise pointless?
-    //         // device_removeds.insert((device,last_owner),false);
-    //         owner_mapping_changeds.insert((last_owner,None));
-    //     }
-    // }
-
     //need to handle gamepad that has player removed from it
     for (entity,owner,_bind_mode) in gamepad_query.iter() {
         let device=Device::Gamepad(entity);
@@ -495,38 +1215,77 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
     //clear modifier_binding_vals with disconnected devices
     if !disconnected_devices.is_empty() {
         modifier_binding_vals.retain(|(device,_),_|!disconnected_devices.contains(device));
+        binding_press_orders.0.retain(|(device,_),_|!disconnected_devices.contains(device));
     }
 
     //
-    let mut not_repeatings : HashSet<(i32, M)> = Default::default();
+    let mut not_repeatings : HashSet<(i32, M)> = Default::default();
+
+    //
+    owner_latched_modifiers.retain(|owner,_|owner_sticky_modifiers.contains_key(owner));
+    sticky_tap_modifiers.retain(|(owner,_),_|owner_sticky_modifiers.contains_key(owner));
 
 
     //on mappings/bindings updated
     //send events for removed mappings ending? also bindings?
     if *owner_bindings_updated {
         *owner_bindings_updated=false;
-        other_device_owners.clear();
+        device_kind_owners.clear();
+        kbm_binding_owners.clear();
+        lone_modifier_pendings.clear();
+
+        //remove owners no longer in owner_bindings, releasing their mappings
+        for owner in owner_mappings.keys().cloned().collect::<Vec<_>>() {
+            if owner_bindings.contains_key(&owner) {
+                continue;
+            }
+
+            for (mapping,_) in owner_mappings.remove(&owner).unwrap() {
+                owner_mapping_changeds.insert((owner,Some(mapping)));
+            }
+
+            owner_primary_mappings.remove(&owner);
+            owner_modifier_mappings.remove(&owner);
+            owner_active_devices.remove(&owner);
+        }
 
         for (&owner,mappings) in owner_bindings.iter() {
             let mut temp_owner_mappings: HashMap<M, HashMap<BindingGroup,MappingBindingInfo>>=HashMap::new();
 
             //collect input in temp mappings
             for ((mapping, bindings),&(scale, primary_dead, modifier_dead)) in mappings.iter() {
-                if bindings.is_empty() {
+                if bindings.is_empty() || bindings.last().unwrap().negated().is_some() {
                     continue;
                 }
 
                 for binding in bindings.iter() {
-                    if binding.is_other_device() {
-                        other_device_owners.insert(owner);
-                        break;
+                    match binding.device_kind() {
+                        Some(DeviceKind::Gamepad)|None => {}
+                        Some(device_kind) => {
+                            device_kind_owners.entry(device_kind).or_default().insert(owner);
+                        }
+                    }
+
+                    if let Some(DeviceKind::Keyboard|DeviceKind::Mouse)=binding.device_kind() {
+                        let binding_owners=kbm_binding_owners.entry(*binding).or_default();
+
+                        if !binding_owners.contains(&owner) {
+                            binding_owners.push(owner);
+                        }
                     }
                 }
 
                 let temp_bindings=temp_owner_mappings.entry(mapping.clone()).or_default();
-                let binding_group=BindingGroup{ modifiers: bindings[0..bindings.len()-1].to_vec(), primary: bindings.last().unwrap().clone() };
-
-                temp_bindings.insert(binding_group,MappingBindingInfo{scale,primary_dead,modifier_dead}); //,binding_val:0.0
+                let modifiers=&bindings[0..bindings.len()-1];
+                let binding_group=BindingGroup{
+                    modifiers: modifiers.iter().filter(|binding|binding.negated().is_none()).cloned().collect(),
+                    negated_modifiers: modifiers.iter().filter_map(|binding|binding.negated()).collect(),
+                    primary: bindings.last().unwrap().clone(),
+                };
+
+                let ordered=ordered_chords.contains(bindings);
+                let interaction=binding_interactions.get(&(mapping.clone(),bindings.clone())).cloned();
+                temp_bindings.insert(binding_group,MappingBindingInfo{scale,primary_dead,modifier_dead,ordered,interaction}); //,binding_val:0.0
             }
 
             //setup primary binding mappings
@@ -590,8 +1349,113 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 }
             }
         }
+
+        //report kbm bindings bound by multiple owners, when split
+        for binding_owners in kbm_binding_owners.values_mut() {
+            binding_owners.sort();
+        }
+
+        for &binding in kbm_binding_owners.keys().filter(|_|*kbm_split) {
+            //a key's Key and LogicalKey are the same key, reported once with the Key if both are bound
+            let same_bindings=same_key_bindings(binding,key_labels);
+
+            if let Binding::LogicalKey(_)=binding {
+                if same_bindings.iter().any(|same_binding|*same_binding!=binding && kbm_binding_owners.contains_key(same_binding)) {
+                    continue;
+                }
+            }
+
+            let mut owners=same_bindings.iter()
+                .filter_map(|binding|kbm_binding_owners.get(binding))
+                .flat_map(|binding_owners|binding_owners.iter())
+                .filter(|&&owner|kbm_owner.contains(owner))
+                .cloned().collect::<Vec<_>>();
+
+            owners.sort();
+            owners.dedup();
+
+            if owners.len()>1 {
+                mapping_events.push(InputMapMessage::KbmBindingConflict { binding, owners });
+            }
+        }
+    }
+
+    //owners of keyboard/mouse/touch are those with bindings for them (after they are updated), keyboard/mouse also limited by kbm_owner
+    let mut kind_owners=device_kind_owners.clone();
+
+    for (kind,owners) in kind_owners.iter_mut() {
+        if *kind!=DeviceKind::Keyboard && *kind!=DeviceKind::Mouse {
+            continue;
+        }
+
+        owners.retain(|&owner|kbm_owner.contains(owner));
+    }
+
+    //handle bind_mode removes
+    //  clear presseds on bind mode
+    for &device in bind_mode_devices.iter() {
+        if !device_bind_mode_lasts.contains(&device) {
+            let owners=device_owners(device,&device_owner,&kind_owners);
+
+            // let Some(&owner)=device_owner.get(&device) else {continue;};
+            // //check last owner is same, otherwise pointless?
+            // // device_removeds.insert((device,owner),true);
+
+            // // let bind_mode_excludes=bind_mode_owner_excludes.get(&owner);
+            // // let bind_mode_includes=bind_mode_owner_includes.get(&owner);
+
+
+            for owner in owners {
+                owner_mapping_changeds.insert((owner,None));
+
+                //
+                let Some(mapping_vals)=owner_mappings.get_mut(&owner) else { continue; };
+
+
+                //clear presseds on bind mode
+
+                for (_mapping,mapping_val) in mapping_vals.iter_mut() {
+                    //remove bind_groups that have device in bindmode, and aren't excluded from it
+                    mapping_val.binding_vals.retain(|(device2,bind_group),_|{
+                        let not_bind_mode=!bind_mode_devices.contains(device2);
+
+                        // let not_bind_mode=not_bind_mode || bind_mode_excludes.contains(&bind_group.primary);
+                        // let not_bind_mode=not_bind_mode || !is_binding_bind_mode(owner,&bind_mode_owner_excludes,&bind_mode_owner_includes,bind_group.primary);
+                        let not_bind_mode=not_bind_mode || !is_binding_bind_mode(&bind_mode_excludes,&bind_mode_includes,bind_group.primary);
+
+                        // let not_bind_mode=not_bind_mode || bind_group.modifiers.len()==bind_group.modifiers.iter().filter(|&&x|bind_mode_excludes.contains(x)).count();
+                        let not_bind_mode=not_bind_mode || bind_group.modifiers.len()==bind_group.modifiers.iter().filter(|&&binding|{
+                            // !is_binding_bind_mode(owner,&bind_mode_owner_excludes,&bind_mode_owner_includes,binding)
+                            !is_binding_bind_mode(&bind_mode_excludes,&bind_mode_includes,binding)
+                        }).count();
+
+                        not_bind_mode
+                    });
+                }
+            }
+
+        }
+    }
+
+    //handle kbm that has player removed from it
+    for (&kind,last_owners) in device_kind_last_owners.iter() {
+        for &owner in last_owners.iter() {
+            if kind_owners.get(&kind).map(|owners|owners.contains(&owner)).unwrap_or_default() {
+                continue;
+            }
+
+            owner_mapping_changeds.insert((owner,None));
+
+            let Some(mapping_vals)=owner_mappings.get_mut(&owner) else { continue; };
+
+            for mapping_val in mapping_vals.values_mut() {
+                mapping_val.binding_vals.retain(|(device,_),_|device.kind()!=kind);
+            }
+        }
     }
 
+    *device_kind_last_owners=kind_owners.clone();
+
     //
     let binding_inputs=binding_input_events.read().map(|&x|x).collect::<Vec<_>>();
 
@@ -614,8 +1478,82 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
 
         if is_bind_mode || binding_input.value == 0.0 {
             modifier_binding_vals.remove(&device_binding);
+            binding_press_orders.0.remove(&device_binding);
         } else {
             modifier_binding_vals.insert(device_binding,binding_input.value);
+
+            //keep order of when pressed, for ordered chords
+            let (press_orders,next_order)=&mut *binding_press_orders;
+
+            press_orders.entry(device_binding).or_insert_with(||{
+                *next_order+=1;
+                *next_order
+            });
+        }
+    }
+
+    //on binding press, release pressed binding_groups that have it as a negated modifier
+    for binding_input in binding_inputs.iter() {
+        if binding_input.value==0.0 || binding_input.immediate {
+            continue;
+        }
+
+        for owner in device_owners(binding_input.device,&device_owner,&kind_owners) {
+            let Some(mapping_vals) = owner_mappings.get_mut(&owner) else { continue; };
+
+            for mapping_val in mapping_vals.values_mut() {
+                let last_len=mapping_val.binding_vals.len();
+
+                mapping_val.binding_vals.retain(|&(device,ref bind_group),_|{
+                    !bind_group.negated_modifiers.contains(&binding_input.binding)
+                        || binding_device(device,binding_input.binding)!=binding_input.device
+                });
+
+                if last_len!=mapping_val.binding_vals.len() {
+                    owner_mapping_changeds.insert((owner,None));
+                }
+            }
+        }
+    }
+
+    //latch/unlatch tapped modifiers, of owners with sticky modifiers
+    for binding_input in binding_inputs.iter() {
+        if binding_input.immediate {
+            continue;
+        }
+
+        for owner in device_owners(binding_input.device,&device_owner,&kind_owners) {
+            let Some(&sticky_modifiers)=owner_sticky_modifiers.get(&owner) else { continue; };
+
+            let is_modifier=owner_modifier_mappings.get(&owner)
+                .map(|modifier_mappings|modifier_mappings.contains_key(&binding_input.binding)).unwrap_or_default();
+
+            //anything else pressed while a modifier is held means it wasn't tapped
+            if binding_input.value!=0.0 {
+                for (&(owner2,modifier),is_tap) in sticky_tap_modifiers.iter_mut() {
+                    if owner2==owner && modifier!=binding_input.binding {
+                        *is_tap=false;
+                    }
+                }
+
+                if is_modifier {
+                    sticky_tap_modifiers.entry((owner,binding_input.binding)).or_insert(true);
+                }
+
+                continue;
+            }
+
+            if !is_modifier || sticky_tap_modifiers.remove(&(owner,binding_input.binding))!=Some(true) {
+                continue;
+            }
+
+            let latcheds=owner_latched_modifiers.entry(owner).or_default();
+
+            if sticky_modifiers==StickyModifiers::Toggle && latcheds.contains(&binding_input.binding) {
+                latcheds.remove(&binding_input.binding);
+            } else {
+                latcheds.insert(binding_input.binding);
+            }
         }
     }
 
@@ -632,15 +1570,14 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
         // let Some(owner)=device_owner.get(&binding_input.device).cloned() else { continue; };
 
         //
-        let owners=if let Device::Other=binding_input.device {
-            other_device_owners.iter().cloned().collect::<Vec<_>>()
-        } else if let Some(&owner)=device_owner.get(&binding_input.device) {
-            vec![owner]
-        } else {
-            Vec::new()
-        };
+        let owners=device_owners(binding_input.device,&device_owner,&kind_owners);
 
         for owner in owners {
+            //latched modifiers are still held
+            if owner_latched_modifiers.get(&owner).map(|latcheds|latcheds.contains(&binding_input.binding)).unwrap_or_default() {
+                continue;
+            }
+
             //
             let Some(modifier_mappings) = owner_modifier_mappings.get(&owner).and_then(|modifier_mappings|modifier_mappings.get(&binding_input.binding)) else {
                 continue;
@@ -659,7 +1596,7 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 // let binding_vals=owner_mapping_binding_vals.entry((owner,mapping.clone())).or_insert_with(||mapping_val.binding_vals.clone());
 
                 //
-                let device_bind_group=(binding_input.device,bind_group.clone());
+                let device_bind_group=(binding_device(binding_input.device,bind_group.primary),bind_group.clone());
                 let binding_val=mapping_val.binding_vals.get(&device_bind_group).cloned().unwrap_or_default();
 
                 if binding_val!=0.0 {
@@ -686,11 +1623,8 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
             let last_dir=if last_val>0.0{1}else if last_val<0.0{-1}else{0};
 
             if last_val!=0.0 {
-                mapping_event_writer.write(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: 0.0, owner });
-            }
-
-            if last_val==0.0 {
-                mapping_event_writer.write(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
+                mapping_events.push(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: 0.0, owner });
+                mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
             }
         } else {
             let Some(mapping_vals)=owner_mappings.get(&owner) else {
@@ -710,14 +1644,14 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 let cur_dir=if cur_val>0.0{1}else if cur_val<0.0{-1}else{0};
 
                 if last_val!=cur_val {
-                    mapping_event_writer.write(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: cur_val, owner });
+                    mapping_events.push(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: cur_val, owner });
                 }
 
                 if cur_dir!=last_dir {
-                    mapping_event_writer.write(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
+                    mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
 
                     if cur_val!=0.0 {
-                        mapping_event_writer.write(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: cur_dir, owner });
+                        mapping_events.push(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: cur_dir, owner });
                     }
                 }
             }
@@ -728,7 +1662,7 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
     //  maybe should handle modifier presses/depresses here?
     //     probably not since modifiers being pressed is handled above
     //     though kinda makes sense
-    //     although reason not to is because kb and ms are considered the same device
+    //     although reason not to is because kb and ms can be mixed in the same chord
     //       but their inputs are received separately
     //       so if say chose to receive kb inputs first and ms second
     //         then could never use ms inputs as modifiers and kb input as primary
@@ -737,14 +1671,40 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
         // let Some(owner)=device_owner.get(&binding_input.device).cloned() else { continue; };
 
         //
-        let owners=if let Device::Other=binding_input.device {
-            other_device_owners.iter().cloned().collect::<Vec<_>>()
-        } else if let Some(&owner)=device_owner.get(&binding_input.device) {
-            vec![owner]
-        } else {
-            Vec::new()
+        let mut owners=device_owners(binding_input.device,&device_owner,&kind_owners);
+
+        //only send to the owner the kbm binding is split to
+        if *kbm_split && !matches!(binding_input.device,Device::Gamepad(_)) {
+            let split_owner=kbm_split_owner(binding_input.binding,&kbm_binding_owners,&kind_owners,key_labels);
+            owners.retain(|&owner|Some(owner)==split_owner);
+        }
+
+        //update owners' active device, also on bind mode
+        let active_threshold=match binding_input.binding {
+            Binding::MouseMoveX|Binding::MouseMoveY
+                |Binding::MouseMovePosX|Binding::MouseMovePosY
+                |Binding::MouseMoveNegX|Binding::MouseMoveNegY
+                => *active_device_mouse_move_threshold,
+            _ => *active_device_threshold,
         };
 
+        if binding_input.value.abs() >= active_threshold {
+            for &owner in owners.iter() {
+                let last_device=owner_active_devices.insert(owner,binding_input.device);
+
+                //only send when switching between kbm and gamepads
+                let is_kbm=|device:Device|matches!(device,Device::Keyboard|Device::Mouse);
+                let changed=match last_device {
+                    Some(last_device) => last_device!=binding_input.device && !(is_kbm(last_device) && is_kbm(binding_input.device)),
+                    None => true,
+                };
+
+                if changed {
+                    mapping_events.push(InputMapMessage::ActiveDeviceChanged { owner, device: binding_input.device });
+                }
+            }
+        }
+
         for owner in owners {
             let is_bind_mode=bind_mode_devices.contains(&binding_input.device);
             // let bind_mode_excludes=bind_mode_owner_excludes.get(&owner);
@@ -756,6 +1716,11 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 continue;
             }
 
+            //pressing anything else stops pending lone modifiers from being pressed on release
+            if !binding_input.immediate && binding_input.value!=0.0 {
+                lone_modifier_pendings.retain(|(pending_owner,_,_,binding_group),_|*pending_owner!=owner || binding_group.primary==binding_input.binding);
+            }
+
             //
             let Some(mapping_vals) = owner_mappings.get_mut(&owner) else { continue; };
 
@@ -790,6 +1755,8 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 }
             }
 
+            let newly_found=founds.is_empty();
+
             if founds.is_empty() {
 
                 // let bind_mode_excludes=bind_mode_owner_excludes.get(&owner);
@@ -806,10 +1773,24 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
 
                     //check modifiers pressed
                     for &modifier_binding in bind_group.modifiers.iter() {
-                        let modifier_val=modifier_binding_vals.get(&(binding_input.device,modifier_binding)).cloned().unwrap_or_default();
+                        let modifier_device=binding_device(binding_input.device,modifier_binding);
+                        let modifier_val=modifier_binding_vals.get(&(modifier_device,modifier_binding)).cloned().unwrap_or_default();
                         let modifier_val = if modifier_val.abs()<binding_info.modifier_dead{0.0}else{modifier_val};
 
-                        if modifier_val== 0.0 || (is_bind_mode &&
+                        //modifier split to another owner
+                        let modifier_val = if *kbm_split && !matches!(modifier_device,Device::Gamepad(_))
+                            && kbm_split_owner(modifier_binding,&kbm_binding_owners,&kind_owners,key_labels)!=Some(owner)
+                        {
+                            0.0
+                        } else {
+                            modifier_val
+                        };
+
+                        //latched sticky modifier
+                        let modifier_latched=owner_latched_modifiers.get(&owner).map(|latcheds|latcheds.contains(&modifier_binding)).unwrap_or_default();
+                        let modifier_val = if modifier_val==0.0 && modifier_latched {1.0} else {modifier_val};
+
+                        if modifier_val== 0.0 || (bind_mode_devices.contains(&modifier_device) &&
                             // !bind_mode_excludes.contains(&modifier_binding)
                             // is_binding_bind_mode(owner,&bind_mode_owner_excludes,&bind_mode_owner_includes,modifier_binding)
                             is_binding_bind_mode(&bind_mode_excludes,&bind_mode_includes,modifier_binding)
@@ -818,17 +1799,95 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                         }
                     }
 
+                    //check negated modifiers not held
+                    for &negated_binding in bind_group.negated_modifiers.iter() {
+                        let negated_device=binding_device(binding_input.device,negated_binding);
+                        let negated_val=modifier_binding_vals.get(&(negated_device,negated_binding)).cloned().unwrap_or_default();
+
+                        if negated_val.abs()>=binding_info.modifier_dead.max(f32::EPSILON) {
+                            return false;
+                        }
+                    }
+
+                    //check modifiers pressed in order, before the primary
+                    if binding_info.ordered {
+                        let mut last_order=0;
+
+                        for &modifier_binding in bind_group.modifiers.iter() {
+                            let modifier_device=binding_device(binding_input.device,modifier_binding);
+                            let Some(order)=binding_press_orders.0.get(&(modifier_device,modifier_binding)).cloned() else {
+                                //not held, so latched, which is pressed before anything else
+                                continue;
+                            };
+
+                            if order<=last_order {
+                                return false;
+                            }
+
+                            last_order=order;
+                        }
+
+                        //immediate primaries aren't stored, so are always last
+                        let primary_order=binding_press_orders.0.get(&(binding_input.device,binding_input.binding)).cloned().unwrap_or(u64::MAX);
+
+                        if primary_order<=last_order {
+                            return false;
+                        }
+                    }
+
                     //
                     true
                 });
 
 
-                if let Some((_mapping,binding_group))=primary_mapping_binding_group_vec.first() {
-                    // let mods_num=binding_group.modifiers.len();
+                //longest of the exclusive ones, shared ones are always used
+                let exclusive_modifiers_num=primary_mapping_binding_group_vec.iter()
+                    .find(|(mapping,_)|!shared_mappings.contains(mapping))
+                    .map(|(_,binding_group)|binding_group.modifiers.len());
+
+                for (mapping2,binding_group2) in primary_mapping_binding_group_vec.iter() {
+                    if *chord_resolution==ChordResolution::All || shared_mappings.contains(mapping2)
+                        || Some(binding_group2.modifiers.len())==exclusive_modifiers_num
+                    {
+                        founds.push((mapping2.clone(),binding_group2.clone(),))
+                    }
+                }
+            }
+
+            //release active shorter chords (of exclusive mappings) contained in the newly pressed ones
+            if newly_found && *chord_resolution==ChordResolution::LongestWins && !binding_input.immediate {
+                for (mapping,binding_group) in founds.iter() {
+                    let primary_dead=mapping_vals.get(mapping).unwrap().binding_infos.get(binding_group).unwrap().primary_dead;
+
+                    if shared_mappings.contains(mapping) || binding_input.value.abs()<primary_dead.max(f32::EPSILON) {
+                        continue;
+                    }
+
+                    for (mapping2,mapping_val2) in mapping_vals.iter_mut() {
+                        if shared_mappings.contains(mapping2) {
+                            continue;
+                        }
+
+                        let last_val=mapping_val2.binding_vals.iter().map(|(_,&v)|v).sum::<f32>();
+                        let last_len=mapping_val2.binding_vals.len();
+
+                        mapping_val2.binding_vals.retain(|(device2,binding_group2),_|{
+                            let contained=binding_group2.modifiers.len()<binding_group.modifiers.len()
+                                && *device2==binding_device(binding_input.device,binding_group2.primary)
+                                && binding_group2.modifiers.iter().chain([&binding_group2.primary])
+                                    .all(|binding2|*binding2==binding_group.primary||binding_group.modifiers.contains(binding2));
+                            !contained
+                        });
+
+                        if last_len==mapping_val2.binding_vals.len() {
+                            continue;
+                        }
+
+                        let cur_val=mapping_val2.binding_vals.iter().map(|(_,&v)|v).sum::<f32>();
+                        send_val_changes(&mut mapping_events,mapping2,owner,last_val,cur_val);
 
-                    for (mapping2,binding_group2) in primary_mapping_binding_group_vec.iter() {
-                        if binding_group.modifiers.len() == binding_group2.modifiers.len() {
-                            founds.push((mapping2.clone(),binding_group2.clone(),))
+                        if mapping_repeats.contains_key(mapping2) {
+                            not_repeatings.insert((owner,mapping2.clone()));
                         }
                     }
                 }
@@ -847,6 +1906,10 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 let last_val=mapping_val.binding_vals.iter().map(|(_,&v)|v).sum::<f32>();
                 let last_dir=if last_val>0.0{1}else if last_val<0.0{-1}else{0};
 
+                //
+                let mapping_inverts=if binding_input.device.kind()==DeviceKind::Gamepad {&owner_gamepad_mapping_inverts} else {&owner_kbm_mapping_inverts};
+                let invert=if mapping_inverts.contains(&(owner,mapping.clone())) {-1.0} else {1.0};
+
                 //
                 if binding_input.immediate { //ie mouse move/scroll
                     // if !modifiers_pressed { //what's this for?
@@ -854,11 +1917,11 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                     // }
 
                     //
-                    let cur_val=binding_input.value;
+                    let cur_val=binding_input.value*invert;
                     let cur_dir=if cur_val>0.0{1}else if cur_val<0.0{-1}else{0};
 
                     //
-                    mapping_event_writer.write(InputMapMessage::TempValueChanged { mapping: mapping.clone(), val: cur_val, owner });
+                    mapping_events.push(InputMapMessage::TempValueChanged { mapping: mapping.clone(), val: cur_val, owner });
 
                     //reset repeating
                     if mapping_repeats.contains_key(&mapping) {
@@ -867,22 +1930,65 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
 
                     //send press/release events (cur_dir will never be 0)
                     if last_dir==cur_dir || last_dir!=0 { //(last_dir!=cur_dir && last_dir!=0)
-                        mapping_event_writer.write(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner }); //0
+                        mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner }); //0
                     }
 
                     if last_dir==0 || last_dir!=cur_dir { //(last_dir!=cur_dir && last_dir!=0)
-                        mapping_event_writer.write(InputMapMessage::JustPressed{ mapping:mapping.clone(), dir: cur_dir, owner }); //1
-                        mapping_event_writer.write(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: cur_dir, owner }); //2
+                        mapping_events.push(InputMapMessage::JustPressed{ mapping:mapping.clone(), dir: cur_dir, owner }); //1
+                        mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: cur_dir, owner }); //2
                     }
 
                     if last_dir==cur_dir || last_dir!=0 {
-                        mapping_event_writer.write(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: last_dir, owner }); //3
+                        mapping_events.push(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: last_dir, owner }); //3
                     }
                 } else {
                     //binding input val
-                    let input_val = if binding_input.value.abs()<binding_info.primary_dead{0.0}else{binding_input.value}*binding_info.scale;
+                    let input_val = if binding_input.value.abs()<binding_info.primary_dead{0.0}else{binding_input.value}*binding_info.scale*invert;
+                    let interaction=binding_info.interaction;
+
+                    //lone modifier, press and release on its release instead
+                    let is_lone_modifier=*lone_modifiers_on_release && binding_group.modifiers.is_empty()
+                        && owner_modifier_mappings.get(&owner).map(|modifier_mappings|modifier_mappings.contains_key(&binding_group.primary)).unwrap_or_default();
+
+                    if is_lone_modifier {
+                        let pending_key=(owner,binding_input.device,mapping.clone(),binding_group.clone());
+
+                        if input_val!=0.0 {
+                            lone_modifier_pendings.insert(pending_key,input_val);
+                        } else if let Some(pending_val)=lone_modifier_pendings.remove(&pending_key) {
+                            send_val_changes(&mut mapping_events,&mapping,owner,last_val,last_val+pending_val);
+                            send_val_changes(&mut mapping_events,&mapping,owner,last_val+pending_val,last_val);
+                        }
+
+                        continue;
+                    }
+
+                    //sticky modifiers unlatched by a non modifier primary
+                    if input_val!=0.0 && owner_sticky_modifiers.contains_key(&owner) {
+                        let is_modifier=owner_modifier_mappings.get(&owner)
+                            .map(|modifier_mappings|modifier_mappings.contains_key(&binding_group.primary)).unwrap_or_default();
+
+                        if !is_modifier && owner_sticky_modifiers.get(&owner)==Some(&StickyModifiers::Sticky) {
+                            owner_latched_modifiers.remove(&owner);
+                        }
+                    }
+
+                    //start/end interaction on the primary's press/release, here so a press and release in the same frame still count
+                    let device_bind_group=(binding_input.device,binding_group.clone());
+                    let last_binding_val=mapping_val.binding_vals.get(&device_bind_group).cloned().unwrap_or_default();
+
+                    if let Some(interaction)=interaction {
+                        if last_binding_val==0.0 && input_val!=0.0 {
+                            mapping_val.interaction_times.insert(device_bind_group.clone(),(0.0,if input_val>0.0{1}else{-1},false));
+                        } else if last_binding_val!=0.0 && input_val==0.0 {
+                            if let Some(interaction_time)=mapping_val.interaction_times.remove(&device_bind_group) {
+                                send_interaction_release(&mut mapping_events,&mapping,owner,interaction,interaction_time);
+                            }
+                        }
+                    }
+
                     // let input_val = if modifiers_pressed {input_val} else {0.0};
-                    mapping_val.binding_vals.insert((binding_input.device,binding_group.clone()),input_val);
+                    mapping_val.binding_vals.insert(device_bind_group,input_val);
 
                     //get cur val
                     // let cur_val=mapping_val.binding_vals.iter().map(|x|*x.1).sum::<f32>();
@@ -891,18 +1997,23 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
 
                     //change event
                     if last_val!=cur_val {
-                        mapping_event_writer.write(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: cur_val, owner });
+                        mapping_events.push(InputMapMessage::ValueChanged { mapping: mapping.clone(), val: cur_val, owner });
                     }
 
                     //
                     if last_dir!=cur_dir {
                         //send press/release event
                         if cur_dir==0 || last_dir!=0 {
-                            mapping_event_writer.write(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
+                            mapping_events.push(InputMapMessage::JustReleased { mapping: mapping.clone(), dir: last_dir, owner });
                         }
 
                         if last_dir==0 || cur_dir!=0 {
-                            mapping_event_writer.write(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: cur_dir, owner });
+                            mapping_events.push(InputMapMessage::JustPressed { mapping: mapping.clone(), dir: cur_dir, owner });
+
+                            //counted here so a press and release in the same frame still count
+                            if let Some(&max_interval)=mapping_multi_taps.get(&mapping) {
+                                multi_tap_press(&mut mapping_events,mapping_val,&mapping,owner,cur_dir,max_interval);
+                            }
                         }
 
                         //reset repeating
@@ -938,7 +2049,7 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
                 mapping_val.repeat_time_accum+=time.delta_secs();
 
                 if mapping_val.repeat_time_accum>=duration {
-                    mapping_event_writer.write(InputMapMessage::Repeat {
+                    mapping_events.push(InputMapMessage::Repeat {
                         mapping: mapping.clone(),
                         dir: cur_dir,
                         delay: duration,
@@ -960,8 +2071,49 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
         }
     }
 
+    //do multi taps, the taps themselves are counted on JustPressed in the binding input loop
+    for mapping in mapping_multi_taps.keys() {
+        for mapping_vals in owner_mappings.values_mut() {
+            let Some(mapping_val)=mapping_vals.get_mut(mapping) else {continue;};
+            mapping_val.tap_time_accum+=time.delta_secs();
+        }
+    }
+
+    //do held interactions, started/ended on press/release in the binding input loop
+    for (&owner,mapping_vals) in owner_mappings.iter_mut() {
+        for (mapping,mapping_val) in mapping_vals.iter_mut() {
+            let MappingVal { binding_infos, binding_vals, interaction_times, .. }=mapping_val;
+
+            //removed (eg chord suppressed, modifier released, bindings changed), sends nothing
+            interaction_times.retain(|device_bind_group,_|{
+                binding_vals.get(device_bind_group).cloned().unwrap_or_default()!=0.0
+            });
+
+            for (device_bind_group,(held_time,dir,fired)) in interaction_times.iter_mut() {
+                let Some(interaction)=binding_infos.get(&device_bind_group.1).and_then(|binding_info|binding_info.interaction) else {
+                    continue;
+                };
+
+                *held_time+=time.delta_secs();
+
+                match interaction {
+                    BindingInteraction::Hold(secs) if !*fired && *held_time>=secs => {
+                        mapping_events.push(InputMapMessage::Hold { mapping: mapping.clone(), dir: *dir, owner });
+                        *fired=true;
+                    }
+                    BindingInteraction::LongPress(secs) if !*fired => {
+                        let progress=if secs>0.0 {(*held_time/secs).min(1.0)} else {1.0};
+                        mapping_events.push(InputMapMessage::LongPress { mapping: mapping.clone(), dir: *dir, progress, owner });
+                        *fired=progress>=1.0;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
     //do bind mode
-    for binding_input in binding_inputs.iter() {
+    for (i,binding_input) in binding_inputs.iter().enumerate() {
         // let Some(owner)=device_owner.get(&binding_input.device).cloned() else { continue; };
         // let owner=device_owner.get(&binding_input.device).cloned();
         let is_bind_mode= bind_mode_devices.contains(&binding_input.device);
@@ -973,27 +2125,43 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
             continue;
         }
 
-        let device_binding=(binding_input.device,binding_input.binding);
+        //a key with a char sends its Key then its LogicalKey, only one of them goes in the chain
+        if let Binding::LogicalKey(_)=binding_input.binding {
+            if !*bind_mode_logical_keys {
+                continue;
+            }
+        } else if let Binding::Key(_)=binding_input.binding {
+            let has_logical_key=binding_inputs.get(i+1).map(|next|{
+                next.device==binding_input.device && matches!(next.binding,Binding::LogicalKey(_))
+            }).unwrap_or_default();
+
+            if *bind_mode_logical_keys && has_logical_key {
+                continue;
+            }
+        }
+
+        let chain_device=bind_chain_device(binding_input.device,&bind_mode_devices);
+        let device_binding=(chain_device,binding_input.binding);
         let has_binding = bind_mode_bindings.contains(&device_binding);
 
         if !has_binding && binding_input.value.abs()>*bind_mode_start_dead {
-            let chain_bindings=bind_mode_chain.entry(binding_input.device).or_default();
+            let chain_bindings=bind_mode_chain.entry(chain_device).or_default();
             chain_bindings.push(binding_input.binding);
 
-            mapping_event_writer.write(InputMapMessage::BindPressed{
+            mapping_events.push(InputMapMessage::BindPressed{
                 // owner,
                 device:binding_input.device,
                 bindings:chain_bindings.clone(),
             });
             bind_mode_bindings.insert(device_binding);
         } else if has_binding && binding_input.value.abs()<*bind_mode_end_dead {
-            let chain_bindings=bind_mode_chain.remove(&binding_input.device).unwrap();
+            let chain_bindings=bind_mode_chain.remove(&chain_device).unwrap();
 
             for &binding in chain_bindings.iter() {
-                bind_mode_bindings.remove(&(binding_input.device,binding));
+                bind_mode_bindings.remove(&(chain_device,binding));
             }
 
-            mapping_event_writer.write(InputMapMessage::BindReleased{
+            mapping_events.push(InputMapMessage::BindReleased{
                 // owner,
                 device:binding_input.device,
                 bindings:chain_bindings,
@@ -1008,6 +2176,38 @@ pub fn mapping_event_system<M: Send + Sync + 'static + Eq + Hash+Clone+core::fmt
     for &device in bind_mode_devices.iter() {
         device_bind_mode_lasts.insert(device);
     }
+
+    //send events, toggle mappings' presses flip their latched val, their other value events are dropped
+    mapping_event_writer.write_batch(toggle_mode_events);
+
+    for event in mapping_events {
+        let toggle_owner_mapping=match &event {
+            InputMapMessage::JustPressed { mapping, owner, .. }
+                |InputMapMessage::JustReleased { mapping, owner, .. }
+                |InputMapMessage::ValueChanged { mapping, owner, .. }
+                |InputMapMessage::TempValueChanged { mapping, owner, .. }
+                |InputMapMessage::Repeat { mapping, owner, .. }
+                => Some((*owner,mapping.clone())).filter(|owner_mapping|owner_toggle_mappings.contains(owner_mapping)),
+            _ => None,
+        };
+
+        let Some(toggle_owner_mapping)=toggle_owner_mapping else {
+            mapping_event_writer.write(event);
+            continue;
+        };
+
+        let InputMapMessage::JustPressed { mapping, dir, owner }=event else {
+            continue;
+        };
+
+        let latched_val=owner_toggle_vals.entry(toggle_owner_mapping).or_default();
+        let last_val=*latched_val;
+        *latched_val=if last_val==dir as f32 {0.0} else {dir as f32};
+
+        let mut toggle_events=Vec::new();
+        send_val_changes(&mut toggle_events,&mapping,owner,last_val,*latched_val);
+        mapping_event_writer.write_batch(toggle_events);
+    }
 }
 
 /*
@@ -1016,4 +2216,83 @@ if a press and release in the same step,
 
 what if dif bindings one axis pos, and another neg, for the same mapping
 
-*/
\ No newline at end of file
+*/
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_dead_zone(dead_zone:&DeadZone,vals:&[(f32,f32)]) {
+        for &(value,expected) in vals {
+            let result=use_dead_zone(value,Some(dead_zone));
+            assert!((result-expected).abs()<0.0001,"{value} gave {result}, expected {expected}");
+        }
+    }
+
+    #[test]
+    fn scroll_pixel_steps_remainder() {
+        let mut accum=0.0;
+        assert_eq!(scroll_pixel_steps(&mut accum,25.0,10.0),2.0);
+        assert_eq!(accum,5.0);
+        assert_eq!(scroll_pixel_steps(&mut accum,4.0,10.0),0.0);
+        assert_eq!(scroll_pixel_steps(&mut accum,1.0,10.0),1.0);
+        assert_eq!(accum,0.0);
+
+        //direction change drops the leftover
+        assert_eq!(scroll_pixel_steps(&mut accum,8.0,10.0),0.0);
+        assert_eq!(scroll_pixel_steps(&mut accum,-15.0,10.0),-1.0);
+        assert_eq!(accum,-5.0);
+    }
+
+    #[test]
+    fn scroll_pixel_lines_reset() {
+        let mut accum=ScrollPixelAccum::default();
+        assert_eq!(scroll_pixel_lines(&mut accum,0.0,8.0,10.0,0.5,1.0),(0.0,0.0));
+        assert_eq!(scroll_pixel_lines(&mut accum,0.0,4.0,10.0,0.5,1.2),(0.0,1.0));
+
+        //idle gap
+        assert_eq!(scroll_pixel_lines(&mut accum,0.0,4.0,10.0,0.5,1.3),(0.0,0.0));
+        assert_eq!(scroll_pixel_lines(&mut accum,0.0,4.0,10.0,0.5,2.0),(0.0,0.0));
+
+        //pixels per line changed
+        assert_eq!(scroll_pixel_lines(&mut accum,8.0,0.0,10.0,0.5,2.1),(0.0,0.0));
+        assert_eq!(scroll_pixel_lines(&mut accum,3.0,0.0,5.0,0.5,2.2),(0.0,0.0));
+        assert_eq!(scroll_pixel_lines(&mut accum,2.0,0.0,5.0,0.5,2.3),(1.0,0.0));
+    }
+
+    #[test]
+    fn dead_zone_none() {
+        assert_eq!(use_dead_zone(0.05,None),0.05);
+        assert_eq!(use_dead_zone(-1.0,None),-1.0);
+    }
+
+    #[test]
+    fn dead_zone_ramp() {
+        let dead_zone=DeadZone { center: 0.0, pos_min: 0.2, pos_max: 0.8, neg_min: -0.2, neg_max: -0.8 };
+
+        assert_dead_zone(&dead_zone,&[
+            (0.0,0.0), (0.1,0.0), (0.2,0.0), (0.5,0.5), (0.8,1.0), (1.0,1.0),
+            (-0.1,0.0), (-0.2,0.0), (-0.5,-0.5), (-0.8,-1.0), (-1.0,-1.0),
+        ]);
+    }
+
+    #[test]
+    fn dead_zone_asymmetric() {
+        let dead_zone=DeadZone { center: 0.0, pos_min: 0.1, pos_max: 0.5, neg_min: -0.3, neg_max: -0.9 };
+
+        assert_dead_zone(&dead_zone,&[
+            (0.1,0.0), (0.3,0.5), (0.5,1.0), (0.7,1.0),
+            (-0.2,0.0), (-0.3,0.0), (-0.6,-0.5), (-0.9,-1.0), (-1.0,-1.0),
+        ]);
+    }
+
+    #[test]
+    fn dead_zone_center() {
+        let dead_zone=DeadZone { center: 0.2, pos_min: 0.1, pos_max: 0.6, neg_min: -0.1, neg_max: -1.0 };
+
+        assert_dead_zone(&dead_zone,&[
+            (0.2,0.0), (0.25,0.0), (0.15,0.0), (0.3,0.0), (0.55,0.5), (0.8,1.0), (1.0,1.0),
+            (0.1,0.0), (-0.35,-0.5), (-0.8,-1.0), (-1.0,-1.0),
+        ]);
+    }
+}
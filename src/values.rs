@@ -4,18 +4,40 @@ use bevy::{prelude::{ Entity, GamepadAxis, GamepadButton, KeyCode, MouseButton},
 
 use serde::Deserialize;
 
+use crate::{GamepadBindMode, GamepadDeadZone};
+
 #[derive(Hash, Eq, PartialEq, Clone, Copy,Debug)]
 pub enum Device {
-    // Touch,
-    // MouseKeyboard
-    Other,
+    Keyboard,
+    Mouse,
+    Touch, //no touch bindings yet
     // Gamepad(usize), //GamepadId
     Gamepad(Entity), //GamepadId
 }
 
+impl Device {
+    pub fn kind(&self) -> DeviceKind {
+        match self {
+            Self::Keyboard => DeviceKind::Keyboard,
+            Self::Mouse => DeviceKind::Mouse,
+            Self::Touch => DeviceKind::Touch,
+            Self::Gamepad(_) => DeviceKind::Gamepad,
+        }
+    }
+}
+
+#[derive(Hash, Eq, PartialEq, Clone, Copy,Debug)]
+pub enum DeviceKind {
+    Keyboard,
+    Mouse,
+    Touch,
+    Gamepad,
+}
+
 #[derive(Clone, Hash, PartialEq, Eq,Debug)]
 pub(super) struct BindingGroup {
     pub modifiers : Vec<Binding>,
+    pub negated_modifiers : Vec<Binding>, //must not be held, stored as the non negated binding
     pub primary : Binding,
 }
 
@@ -24,6 +46,8 @@ pub(super)struct MappingBindingInfo {
     pub scale : f32,
     pub primary_dead : f32,
     pub modifier_dead : f32,
+    pub ordered : bool, //modifiers must be pressed in order, before the primary
+    pub interaction : Option<BindingInteraction>,
     // pub binding_val : f32,
 }
 
@@ -34,10 +58,54 @@ pub(super)struct MappingVal {
 
     pub repeat_time_accum : f32, //system time
     pub repeating:bool,
+
+    pub tap_dir : i32,
+    pub tap_count : i32,
+    pub tap_time_accum : f32, //system time, since last tap
+
+    pub interaction_times:HashMap<(Device,BindingGroup),(f32,i32,bool)>, //[device,binding_group]=(held_time,dir,fired), of pressed ones with interactions
+}
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum BindingInteraction {
+    Tap(f32), //released before secs
+    Hold(f32), //held for secs
+    LongPress(f32), //held for secs, with progress sent while held
+    PressRelease, //pressed then released
+}
+
+#[derive(Clone, PartialEq, Eq,Debug)]
+pub(super) struct GamepadInfo {
+    pub name : String,
+    pub vendor_id : Option<u16>,
+    pub product_id : Option<u16>,
+}
+
+pub(super) struct DisconnectedGamepad {
+    pub info : GamepadInfo,
+    pub owner : i32,
+    pub dead_zone : Option<GamepadDeadZone>,
+    pub bind_mode : Option<GamepadBindMode>,
+    pub time : f32, //real time
+}
+
+#[derive(Default)]
+pub(super) struct ScrollPixelAccum {
+    pub x : f32,
+    pub y : f32,
+    pub pixels_per_line : f32, //the x/y were accumulated with
+    pub time : f32, //real time, of last pixel scroll
+}
+
+pub(super) struct CalibrationState {
+    pub start_time : f32, //real time
+    pub rest_ranges : HashMap<GamepadAxis,(f32,f32)>, //[axis]=(min,max)
+    pub full_ranges : HashMap<GamepadAxis,(f32,f32)>, //[axis]=(min,max)
 }
 
-#[derive(Clone,Debug)]
+#[derive(Clone,Debug,PartialEq)]
 pub struct DeadZone {
+    pub center : f32, //resting point of the axis, min/max are relative to it
     pub pos_min : f32,
     pub pos_max : f32,
     pub neg_min : f32,
@@ -46,7 +114,7 @@ pub struct DeadZone {
 
 impl Default for DeadZone {
     fn default() -> Self {
-        Self { pos_min: 0.0, pos_max: 1.0, neg_min: 0.0, neg_max: -1.0 }
+        Self { center: 0.0, pos_min: 0.0, pos_max: 1.0, neg_min: 0.0, neg_max: -1.0 }
     }
 }
 
@@ -88,8 +156,15 @@ pub enum Binding {
     GamepadAxis(GamepadAxis),
     MouseButton(MouseButton),
     Key(KeyCode),
+    LogicalKey(char), //layout aware key, lowercase character from the keyboard layout, shifted symbols aren't folded, eg '1' isn't sent while shift is held, '!' is
     // ModifierKey(Vec<KeyCode>),
     GamepadButton(GamepadButton),
+
+    //negated modifiers, for chords that require them to not be held, eg [NotKey(ShiftLeft),Key(KeyS)]
+    NotKey(KeyCode),
+    NotLogicalKey(char),
+    NotMouseButton(MouseButton),
+    NotGamepadButton(GamepadButton),
 }
 
 impl std::str::FromStr for Binding {
@@ -116,16 +191,18 @@ impl ToString for &Binding {
 impl Binding {
     pub fn get_inner_string(&self) -> String {
         match self {
-            Self::Key(x) => format!("{x:?}"),
+            Self::Key(x)|Self::NotKey(x) => format!("{x:?}"),
+            Self::LogicalKey(x)|Self::NotLogicalKey(x) => x.to_uppercase().to_string(),
             Self::GamepadAxis(x) => format!("{x:?}"),
-            Self::GamepadButton(x) => format!("{x:?}"),
-            Self::MouseButton(x) => format!("{x:?}"),
+            Self::GamepadButton(x)|Self::NotGamepadButton(x) => format!("{x:?}"),
+            Self::MouseButton(x)|Self::NotMouseButton(x) => format!("{x:?}"),
             x => x.to_string(),
         }
     }
     pub fn get_outer_string(&self) -> &str {
         match self {
             Self::Key(_) => "Key",
+            Self::LogicalKey(_) => "LogicalKey",
             Self::GamepadAxis(_) => "GamepadAxis",
             Self::GamepadButton(_) => "GamepadButton",
             Self::MouseButton(_) => "MouseButton",
@@ -153,35 +230,46 @@ impl Binding {
 
             Self::GamepadAxisPos(_) => "GamepadAxisPos",
             Self::GamepadAxisNeg(_) => "GamepadAxisNeg",
+            Self::NotKey(_) => "NotKey",
+            Self::NotLogicalKey(_) => "NotLogicalKey",
+            Self::NotMouseButton(_) => "NotMouseButton",
+            Self::NotGamepadButton(_) => "NotGamepadButton",
             Self::None => "None",
 
         }
     }
 
-    pub fn is_gamepad_device(&self) -> bool {
-        if let Self::GamepadAxisPos(_)|Self::GamepadAxisNeg(_)|Self::GamepadAxis(_)|Self::GamepadButton(_)=self {
-            true
-        } else {
-            false
+    pub fn device_kind(&self) -> Option<DeviceKind> {
+        match self {
+            Self::MouseMoveX|Self::MouseMoveY
+            |Self::MouseMovePosX|Self::MouseMovePosY
+            |Self::MouseMoveNegX|Self::MouseMoveNegY
+            |Self::MouseScrollPixelX|Self::MouseScrollPixelY
+            |Self::MouseScrollPixelPosX|Self::MouseScrollPixelPosY
+            |Self::MouseScrollPixelNegX|Self::MouseScrollPixelNegY
+            |Self::MouseScrollLineX|Self::MouseScrollLineY
+            |Self::MouseScrollLinePosX|Self::MouseScrollLinePosY
+            |Self::MouseScrollLineNegX|Self::MouseScrollLineNegY
+            |Self::MouseButton(_)|Self::NotMouseButton(_)
+            => Some(DeviceKind::Mouse),
+            Self::Key(_)|Self::LogicalKey(_)|Self::NotKey(_)|Self::NotLogicalKey(_) => Some(DeviceKind::Keyboard),
+            Self::GamepadAxisPos(_)|Self::GamepadAxisNeg(_)|Self::GamepadAxis(_)|Self::GamepadButton(_)|Self::NotGamepadButton(_) => Some(DeviceKind::Gamepad),
+            Self::None => None,
         }
     }
 
-    pub fn is_other_device(&self) -> bool {
-        if let Self::MouseMoveX|Self::MouseMoveY
-        |Self::MouseMovePosX|Self::MouseMovePosY
-        |Self::MouseMoveNegX|Self::MouseMoveNegY
-        |Self::MouseScrollPixelX|Self::MouseScrollPixelY
-        |Self::MouseScrollPixelPosX|Self::MouseScrollPixelPosY
-        |Self::MouseScrollPixelNegX|Self::MouseScrollPixelNegY
-        |Self::MouseScrollLineX|Self::MouseScrollLineY
-        |Self::MouseScrollLinePosX|Self::MouseScrollLinePosY
-        |Self::MouseScrollLineNegX|Self::MouseScrollLineNegY
-        |Self::MouseButton(_)
-        |Self::Key(_)
-        = self {
-            true
-        } else {
-            false
+    //the binding a negated modifier requires to not be held
+    pub fn negated(&self) -> Option<Binding> {
+        match *self {
+            Self::NotKey(x) => Some(Self::Key(x)),
+            Self::NotLogicalKey(x) => Some(Self::LogicalKey(x)),
+            Self::NotMouseButton(x) => Some(Self::MouseButton(x)),
+            Self::NotGamepadButton(x) => Some(Self::GamepadButton(x)),
+            _ => None,
         }
     }
+
+    pub fn is_gamepad_device(&self) -> bool {
+        self.device_kind()==Some(DeviceKind::Gamepad)
+    }
 }